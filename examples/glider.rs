@@ -0,0 +1,42 @@
+//! Records a glider crossing a small torus into `glider.gif`.
+//!
+//! ```sh
+//! cargo run --example glider
+//! ```
+
+use std::path::Path;
+
+use gol_img::{
+    record::{GifRecorder, Recorder},
+    Ages, Boundary, EngineKind, Pattern, Renderer, Rule,
+};
+
+fn main() -> anyhow::Result<()> {
+    let (width, height) = (12, 12);
+    let glider = Pattern::parse_rle("x = 3, y = 3, rule = B3/S23\nbo$2bo$3o!")?;
+    let mut grid = gol_img::new_grid(width, height);
+    glider.place(&mut grid, (0, 0), false);
+
+    let mut engine =
+        EngineKind::Packed.build(width, height, Rule::CONWAY, Some(Boundary::Torus))?;
+    engine.load(&grid);
+    let mut ages = Ages::new(width, height);
+    ages.reset(&grid);
+    let renderer = Renderer {
+        decay: 4,
+        ..Renderer::default()
+    };
+
+    // A glider moves by one cell diagonally every 4 generations, so it is
+    // back where it started after 4 times the side of the board.
+    let mut recorder = GifRecorder::new(Path::new("glider.gif"), 100, false)?;
+    for _ in 0..4 * width {
+        recorder.push(renderer.render(&grid, &ages))?;
+        engine.step();
+        engine.store(&mut grid);
+        ages.update(&grid, 1);
+    }
+    Box::new(recorder).finish()?;
+    println!("wrote glider.gif");
+    Ok(())
+}
@@ -0,0 +1,47 @@
+//! Runs symmetric soups until they die out or settle into oscillators, the
+//! way a census of the outcomes of random boards would.
+//!
+//! ```sh
+//! cargo run --example soup -- 42
+//! ```
+
+use gol_img::{
+    history::{Fate, History},
+    Boundary, EngineKind, Rule, SeedSequence, Seeder,
+};
+
+fn main() -> anyhow::Result<()> {
+    let seed = match std::env::args().nth(1) {
+        Some(seed) => seed.parse()?,
+        None => 42,
+    };
+    let seeder: Seeder = "symmetric:symmetry=d4,size=16".parse()?;
+    let (width, height) = (64, 64);
+    let mut grid = gol_img::new_grid(width, height);
+    let mut engine = EngineKind::Packed.build(width, height, Rule::CONWAY, Some(Boundary::Dead))?;
+    let mut seeds = SeedSequence::new(seed);
+    for _ in 0..5 {
+        let seed = seeds.next_seed();
+        seeder.seed(&mut grid, seed, 0.5)?;
+        engine.load(&grid);
+        let mut history = History::new(64);
+        let mut fate = None;
+        for generation in 0..10_000u64 {
+            engine.store(&mut grid);
+            if !grid.iter().any(|&alive| alive) {
+                fate = Some((Fate::Extinct, generation));
+                break;
+            }
+            if let Some(period) = history.push(&grid) {
+                fate = Some((Fate::Periodic(period as u64), generation));
+                break;
+            }
+            engine.step();
+        }
+        match fate {
+            Some((fate, generation)) => println!("{seeder} seed {seed}: {fate:?} at {generation}"),
+            None => println!("{seeder} seed {seed}: still evolving"),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,73 @@
+use ndarray::{Array2, Zip};
+
+use crate::life::Grid;
+
+/// Number of generations each cell has been in its current state: live
+/// cells count up from 1 at birth and dead cells count down from -1 at death.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Ages(Array2<i32>);
+
+impl Ages {
+    /// Age of dead cells that have never been alive since the last reset.
+    pub const NEVER_ALIVE: i32 = i32::MIN;
+
+    /// Creates the ages of an empty board of `width` columns and `height`
+    /// rows.
+    pub fn new(width: usize, height: usize) -> Self {
+        Self(Array2::from_elem((height, width), Self::NEVER_ALIVE))
+    }
+
+    /// Restarts the ages from `grid`, whose live cells are considered newborn.
+    pub fn reset(&mut self, grid: &Grid) {
+        Zip::from(&mut self.0)
+            .and(grid)
+            .par_for_each(|age, &alive| {
+                *age = if alive { 1 } else { Self::NEVER_ALIVE };
+            });
+    }
+
+    /// Ages the cells by `generations`, `grid` being the new state of the
+    /// board. Cells changing state are considered to have done so during the
+    /// last generation.
+    pub fn update(&mut self, grid: &Grid, generations: u64) {
+        let generations = generations.min(i32::MAX as u64) as i32;
+        Zip::from(&mut self.0)
+            .and(grid)
+            .par_for_each(|age, &alive| {
+                *age = match (*age > 0, alive) {
+                    (true, true) => age.saturating_add(generations),
+                    (false, false) => age.saturating_sub(generations),
+                    (false, true) => 1,
+                    (true, false) => -1,
+                };
+            });
+    }
+
+    /// Age of the cell at `(x, y)`.
+    pub fn get(&self, x: usize, y: usize) -> i32 {
+        self.0[(y, x)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::life::new_grid;
+
+    #[test]
+    fn counts_generations_in_current_state() {
+        let mut grid = new_grid(3, 1);
+        grid[(0, 0)] = true;
+        grid[(0, 1)] = true;
+        let mut ages = Ages::new(3, 1);
+        ages.reset(&grid);
+        ages.update(&grid, 4);
+        grid[(0, 1)] = false;
+        grid[(0, 2)] = true;
+        ages.update(&grid, 1);
+        assert_eq!([ages.get(0, 0), ages.get(1, 0), ages.get(2, 0)], [6, -1, 1]);
+        grid[(0, 1)] = false;
+        ages.update(&grid, 2);
+        assert_eq!(ages.get(1, 0), -3);
+    }
+}
@@ -0,0 +1,137 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+
+/// File written under a temporary name in the directory of its destination
+/// and renamed into place once complete, so that readers of the destination
+/// never see it partially written. The temporary file is deleted if dropped
+/// before being committed.
+#[derive(Debug)]
+pub struct AtomicFile {
+    file: File,
+    temp: Option<PathBuf>,
+    path: PathBuf,
+    sync: bool,
+}
+
+impl AtomicFile {
+    /// Starts writing the file at `path`. If `sync` is set, its content is
+    /// flushed to the disk before it is renamed and the rename itself before
+    /// [`AtomicFile::commit`] returns.
+    pub fn create(path: &Path, sync: bool) -> io::Result<Self> {
+        let name = path.file_name().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "the path has no file name")
+        })?;
+        let mut temp_name = std::ffi::OsString::from(".");
+        temp_name.push(name);
+        temp_name.push(format!(".{}.tmp", std::process::id()));
+        let temp = path.with_file_name(temp_name);
+        Ok(Self {
+            file: File::create(&temp)?,
+            temp: Some(temp),
+            path: path.to_path_buf(),
+            sync,
+        })
+    }
+
+    /// Other handle on the temporary file, for writers that cannot be turned
+    /// back into the [`AtomicFile`] they wrap.
+    pub fn try_clone(&self) -> io::Result<File> {
+        self.file.try_clone()
+    }
+
+    /// Renames the file into place.
+    pub fn commit(mut self) -> io::Result<()> {
+        if self.sync {
+            self.file.sync_all()?;
+        }
+        let temp = self.temp.take().unwrap();
+        if let Err(err) = fs::rename(&temp, &self.path) {
+            let _ = fs::remove_file(temp);
+            return Err(err);
+        }
+        #[cfg(unix)]
+        if self.sync {
+            if let Some(parent) = self.path.parent() {
+                let parent = if parent.as_os_str().is_empty() {
+                    Path::new(".")
+                } else {
+                    parent
+                };
+                File::open(parent)?.sync_all()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Write for AtomicFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.file.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+impl Seek for AtomicFile {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.file.seek(pos)
+    }
+}
+
+impl Drop for AtomicFile {
+    fn drop(&mut self) {
+        if let Some(ref temp) = self.temp {
+            let _ = fs::remove_file(temp);
+        }
+    }
+}
+
+/// Atomically writes the file at `path` with `contents`, see [`AtomicFile`].
+pub fn write(
+    path: &Path,
+    sync: bool,
+    contents: impl FnOnce(&mut BufWriter<AtomicFile>) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    let file = AtomicFile::create(path, sync)
+        .with_context(|| format!("failed to create {}", path.display()))?;
+    let mut writer = BufWriter::new(file);
+    contents(&mut writer)?;
+    writer
+        .into_inner()
+        .map_err(|err| err.into_error())
+        .and_then(AtomicFile::commit)
+        .with_context(|| format!("failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replaces_destination_on_commit_only() {
+        let dir = std::env::temp_dir().join(format!("gol_img_atomic_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("output.txt");
+        fs::write(&path, "old").unwrap();
+        let mut file = AtomicFile::create(&path, true).unwrap();
+        file.write_all(b"new").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "old");
+        file.commit().unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        let result = write(&path, false, |writer| {
+            writer.write_all(b"partial")?;
+            Err(anyhow::anyhow!("interrupted"))
+        });
+        assert!(result.is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,118 @@
+use clap::ValueEnum;
+
+use crate::life::Grid;
+
+/// Topology used to look up neighbours that fall outside of the board.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Boundary {
+    /// Opposite edges are glued together
+    #[default]
+    Torus,
+    /// Cells outside the board are always dead
+    Dead,
+    /// Cells outside the board are always alive
+    Alive,
+    /// Left and right edges wrap, top and bottom wrap with a horizontal flip
+    Klein,
+    /// Both pairs of edges wrap with a flip of the other axis (cross-surface)
+    #[value(alias = "cross-surface")]
+    Projective,
+    /// Edges mirror the cells next to them
+    Reflect,
+}
+
+impl Boundary {
+    /// Maps the possibly out of range coordinates `(x, y)` to a cell of a
+    /// `width` by `height` board, or `None` when the cell lies outside of a
+    /// board with fixed edges.
+    pub fn map(self, x: isize, y: isize, width: usize, height: usize) -> Option<(usize, usize)> {
+        if (0..width as isize).contains(&x) && (0..height as isize).contains(&y) {
+            return Some((x as usize, y as usize));
+        }
+        let flip = |v: usize, n: usize, flipped: bool| if flipped { n - 1 - v } else { v };
+        match self {
+            Boundary::Torus => Some((wrap(x, width).0, wrap(y, height).0)),
+            Boundary::Dead | Boundary::Alive => None,
+            Boundary::Klein => {
+                let (y, flipped) = wrap(y, height);
+                Some((flip(wrap(x, width).0, width, flipped), y))
+            }
+            Boundary::Projective => {
+                let (wx, x_flipped) = wrap(x, width);
+                let (wy, y_flipped) = wrap(y, height);
+                Some((flip(wx, width, y_flipped), flip(wy, height, x_flipped)))
+            }
+            Boundary::Reflect => Some((reflect(x, width), reflect(y, height))),
+        }
+    }
+
+    /// State of the cell at `(x, y)`, following the topology for cells
+    /// outside of the board.
+    pub fn cell(self, grid: &Grid, x: isize, y: isize) -> bool {
+        let (height, width) = grid.dim();
+        match self.map(x, y, width, height) {
+            Some((x, y)) => grid[(y, x)],
+            None => self == Boundary::Alive,
+        }
+    }
+}
+
+/// Wraps `v` into `0..n`, also returning whether an odd number of edges was
+/// crossed.
+fn wrap(v: isize, n: usize) -> (usize, bool) {
+    let n = n as isize;
+    (v.rem_euclid(n) as usize, v.div_euclid(n) % 2 != 0)
+}
+
+fn reflect(v: isize, n: usize) -> usize {
+    let m = v.rem_euclid(2 * n as isize) as usize;
+    if m < n {
+        m
+    } else {
+        2 * n - 1 - m
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inner_cells_are_unchanged() {
+        for boundary in Boundary::value_variants() {
+            assert_eq!(boundary.map(3, 2, 8, 5), Some((3, 2)));
+        }
+    }
+
+    #[test]
+    fn torus_wraps_both_axes() {
+        assert_eq!(Boundary::Torus.map(-1, -1, 8, 5), Some((7, 4)));
+        assert_eq!(Boundary::Torus.map(8, 5, 8, 5), Some((0, 0)));
+    }
+
+    #[test]
+    fn klein_flips_across_vertical_edges_only() {
+        assert_eq!(Boundary::Klein.map(-1, 2, 8, 5), Some((7, 2)));
+        assert_eq!(Boundary::Klein.map(1, -1, 8, 5), Some((6, 4)));
+        assert_eq!(Boundary::Klein.map(1, 5, 8, 5), Some((6, 0)));
+    }
+
+    #[test]
+    fn projective_flips_across_every_edge() {
+        assert_eq!(Boundary::Projective.map(-1, 1, 8, 5), Some((7, 3)));
+        assert_eq!(Boundary::Projective.map(1, 5, 8, 5), Some((6, 0)));
+    }
+
+    #[test]
+    fn reflect_mirrors_edges() {
+        assert_eq!(Boundary::Reflect.map(-1, 5, 8, 5), Some((0, 4)));
+        assert_eq!(Boundary::Reflect.map(8, -1, 8, 5), Some((7, 0)));
+    }
+
+    #[test]
+    fn fixed_edges_use_constant_state() {
+        let grid = Grid::from_elem((3, 3), false);
+        assert!(!Boundary::Dead.cell(&grid, -1, 0));
+        assert!(Boundary::Alive.cell(&grid, 3, 3));
+    }
+}
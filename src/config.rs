@@ -0,0 +1,373 @@
+use std::{ffi::OsString, fs, path::PathBuf};
+
+use anyhow::{anyhow, bail, Context};
+use clap::{parser::ValueSource, Arg, ArgAction, ArgMatches, Command, Id, Parser};
+use toml::{Table, Value};
+
+/// Prefix of the environment variables setting the options.
+const ENV_PREFIX: &str = "GOL_IMG_";
+
+/// Arguments choosing the configuration, which cannot be set by it.
+const CONFIG_ARGS: [&str; 3] = ["config", "profile", "print_config"];
+
+/// Parses the command line into `T`, the `GOL_IMG_*` environment variables
+/// and then the options of the `--config` file taking the place of missing
+/// arguments before their defaults. Also returns the effective value of
+/// every option, see [`effective`].
+pub fn parse<T: Parser>() -> anyhow::Result<(T, Table)> {
+    parse_from(std::env::args_os())
+}
+
+/// [`parse`] from the given command line.
+pub fn parse_from<T: Parser>(
+    args: impl IntoIterator<Item = impl Into<OsString> + Clone>,
+) -> anyhow::Result<(T, Table)> {
+    let args: Vec<OsString> = args.into_iter().map(Into::into).collect();
+    let original = with_env(T::command());
+    let mut command = original.clone();
+    // Only the configuration file is needed at first, the other arguments
+    // may be missing until its options are added.
+    let early = command
+        .clone()
+        .ignore_errors(true)
+        .try_get_matches_from(&args)
+        .unwrap_or_else(|err| err.exit());
+    let mut configured = Vec::new();
+    if let Some(path) = early.get_one::<PathBuf>("config") {
+        let profile = early.get_one::<String>("profile").map(String::as_str);
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("failed to read config {}", path.display()))?;
+        (command, configured) = options(&content, profile)
+            .and_then(|options| apply(command, &options))
+            .with_context(|| format!("invalid config {}", path.display()))?;
+    }
+    let unbuilt = command.clone();
+    let mut matches = command
+        .try_get_matches_from_mut(&args)
+        .unwrap_or_else(|err| err.exit());
+    // Clap ignores defaults when checking conflicts, so the options of the
+    // configuration file clashing with arguments or environment variables are
+    // dropped by hand, and the command line validated again without them.
+    let overridden = overridden(&command, &matches, &configured);
+    if !overridden.is_empty() {
+        command = overridden.into_iter().fold(unbuilt, |command, id| {
+            let arg = original.get_arguments().find(|arg| *arg.get_id() == id);
+            let arg = arg
+                .expect("configured arguments come from the command")
+                .clone();
+            command.mut_arg(id, |_| arg)
+        });
+        matches = command
+            .try_get_matches_from_mut(&args)
+            .unwrap_or_else(|err| err.exit());
+    }
+    let parsed = T::from_arg_matches(&matches)
+        .map_err(|err| err.format(&mut command))
+        .unwrap_or_else(|err| err.exit());
+    Ok((parsed, effective(&command, &matches)))
+}
+
+/// Lets every argument of `command` be set with an environment variable
+/// named after it, such as `GOL_IMG_MAX_ITER` for `--max-iter`.
+fn with_env(command: Command) -> Command {
+    command.mut_args(|arg| match arg.get_id().as_str() {
+        "print_config" => arg,
+        id => {
+            let name = format!("{ENV_PREFIX}{}", id.to_uppercase());
+            arg.env(name)
+        }
+    })
+}
+
+/// Options of the configuration file `content`, the ones of its
+/// `[profile.<profile>]` table overriding the top-level ones.
+fn options(content: &str, profile: Option<&str>) -> anyhow::Result<Table> {
+    let mut options: Table = content.parse()?;
+    let profiles = match options.remove("profile") {
+        Some(Value::Table(profiles)) => profiles,
+        Some(_) => bail!("`profile` must be a table of profiles"),
+        None => Table::new(),
+    };
+    if let Some(name) = profile {
+        match profiles.get(name) {
+            Some(Value::Table(profile)) => options.extend(profile.clone()),
+            Some(_) => bail!("profile `{name}` must be a table"),
+            None => bail!("no profile `{name}`"),
+        }
+    }
+    Ok(options)
+}
+
+/// Turns `options`, keyed by the long names of the arguments, into the
+/// default values of the arguments of `command`, also returning the
+/// arguments they set.
+fn apply(mut command: Command, options: &Table) -> anyhow::Result<(Command, Vec<Id>)> {
+    let mut configured = Vec::new();
+    for (key, value) in options {
+        let id = command
+            .get_arguments()
+            .find(|arg| {
+                arg.get_long() == Some(key) && !CONFIG_ARGS.contains(&arg.get_id().as_str())
+            })
+            .ok_or_else(|| anyhow!("unknown option `{key}`"))?
+            .get_id()
+            .clone();
+        let value = match value {
+            Value::String(value) => value.clone(),
+            Value::Integer(value) => value.to_string(),
+            Value::Float(value) => value.to_string(),
+            Value::Boolean(value) => value.to_string(),
+            _ => bail!("invalid value for `{key}`: expected a string, a number or a boolean"),
+        };
+        command = command.mut_arg(&id, |arg| arg.default_value(value).required(false));
+        configured.push(id);
+    }
+    Ok((command, configured))
+}
+
+/// Arguments among `configured` that were left to the configuration file
+/// but cannot be used along with an argument set on the command line or in
+/// the environment.
+fn overridden(command: &Command, matches: &ArgMatches, configured: &[Id]) -> Vec<Id> {
+    let explicit: Vec<_> = command
+        .get_arguments()
+        .filter(|arg| {
+            matches!(
+                matches.value_source(arg.get_id().as_str()),
+                Some(ValueSource::CommandLine | ValueSource::EnvVariable)
+            )
+        })
+        .collect();
+    command
+        .get_arguments()
+        .filter(|arg| {
+            configured.contains(arg.get_id())
+                && matches.value_source(arg.get_id().as_str()) == Some(ValueSource::DefaultValue)
+                && explicit.iter().any(|other| conflict(command, arg, other))
+        })
+        .map(|arg| arg.get_id().clone())
+        .collect()
+}
+
+/// Whether `a` and `b` cannot be used together, either directly or as
+/// members of a group of mutually exclusive arguments.
+fn conflict(command: &Command, a: &Arg, b: &Arg) -> bool {
+    let excludes = |a: &Arg, b: &Arg| {
+        command
+            .get_arg_conflicts_with(a)
+            .iter()
+            .any(|arg| arg.get_id() == b.get_id())
+    };
+    let grouped = command.get_groups().any(|group| {
+        !group.clone().is_multiple()
+            && group.get_args().any(|id| id == a.get_id())
+            && group.get_args().any(|id| id == b.get_id())
+    });
+    excludes(a, b) || excludes(b, a) || grouped
+}
+
+/// Value of every option set in `matches`, whether by the command line, the
+/// environment, the configuration file or a default, as the configuration
+/// file would set them.
+fn effective(command: &Command, matches: &ArgMatches) -> Table {
+    command
+        .get_arguments()
+        .filter(|arg| !CONFIG_ARGS.contains(&arg.get_id().as_str()))
+        .filter_map(|arg| {
+            let key = arg.get_long()?;
+            let raw = matches.get_raw(arg.get_id().as_str())?.next()?.to_str()?;
+            let value = match arg.get_action() {
+                ArgAction::SetTrue | ArgAction::SetFalse => Value::Boolean(raw == "true"),
+                ArgAction::Help | ArgAction::Version => return None,
+                _ => match (raw.parse(), raw.parse()) {
+                    (Ok(integer), _) => Value::Integer(integer),
+                    // Integers too large for TOML are kept as strings.
+                    (_, Ok(float)) if raw.contains('.') => Value::Float(float),
+                    _ => Value::String(raw.to_string()),
+                },
+            };
+            Some((key.to_string(), value))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Parser, Debug, PartialEq)]
+    struct Options {
+        #[arg(long)]
+        config: Option<PathBuf>,
+        #[arg(long, requires = "config")]
+        profile: Option<String>,
+        #[arg(long)]
+        print_config: bool,
+        #[arg(long)]
+        width: usize,
+        #[arg(long, default_value_t = 0.15)]
+        fill: f64,
+        #[arg(long)]
+        seed: Option<u64>,
+        #[arg(long)]
+        fsync: bool,
+        #[arg(long, default_value = "output.png")]
+        output: String,
+        #[arg(long)]
+        delay: Option<u64>,
+        #[arg(long, group = "recording")]
+        gif: Option<String>,
+        #[arg(long, group = "recording")]
+        apng: Option<String>,
+        #[arg(long, conflicts_with = "seed_image")]
+        pattern: Option<String>,
+        #[arg(long)]
+        seed_image: Option<String>,
+        #[arg(long, default_value = "uniform", conflicts_with_all = ["pattern", "seed_image"])]
+        seeder: String,
+    }
+
+    fn write_config(name: &str, content: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("gol_img_{name}_{}.toml", std::process::id()));
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    const CONFIG: &str = r#"
+        width = 64
+        fill = 0.3
+        seed = "18446744073709551615"
+        delay = 100
+
+        [profile.night]
+        fill = 0.1
+        fsync = true
+    "#;
+
+    #[test]
+    fn command_line_overrides_profile_and_config() {
+        let path = write_config("layers", CONFIG);
+        let config = path.to_str().unwrap();
+        let (options, _) = parse_from::<Options>(["gol_img", "--config", config]).unwrap();
+        assert_eq!((options.width, options.fill), (64, 0.3));
+        assert_eq!(options.seed, Some(u64::MAX));
+        assert!(!options.fsync);
+        let (options, _) = parse_from::<Options>([
+            "gol_img",
+            "--config",
+            config,
+            "--profile",
+            "night",
+            "--width",
+            "8",
+        ])
+        .unwrap();
+        assert_eq!((options.width, options.fill), (8, 0.1));
+        assert!(options.fsync);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn environment_overrides_config() {
+        // Changing the environment of this process would race with the other
+        // tests, so the checks run in a child process with its own.
+        let output = std::process::Command::new(std::env::current_exe().unwrap())
+            .args(["config::tests::environment_layer", "--exact", "--ignored"])
+            .env("GOL_IMG_DELAY", "250")
+            .env("GOL_IMG_APNG", "b.png")
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "{}{}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    #[test]
+    #[ignore = "needs the environment set by environment_overrides_config"]
+    fn environment_layer() {
+        let path = write_config("environment", CONFIG);
+        let config = path.to_str().unwrap();
+        let (options, _) = parse_from::<Options>(["gol_img", "--config", config]).unwrap();
+        assert_eq!(options.delay, Some(250));
+        let (options, _) =
+            parse_from::<Options>(["gol_img", "--config", config, "--delay", "5"]).unwrap();
+        assert_eq!(options.delay, Some(5));
+        fs::write(&path, "width = 8\ngif = \"a.gif\"").unwrap();
+        let (options, _) = parse_from::<Options>(["gol_img", "--config", config]).unwrap();
+        assert_eq!(
+            (options.gif, options.apng.as_deref()),
+            (None, Some("b.png"))
+        );
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn command_line_overrides_exclusive_config_options() {
+        let path = write_config(
+            "exclusive",
+            "width = 8\ngif = \"a.gif\"\npattern = \"glider.rle\"",
+        );
+        let config = path.to_str().unwrap();
+        let (options, _) =
+            parse_from::<Options>(["gol_img", "--config", config, "--apng", "b.png"]).unwrap();
+        assert_eq!(options.gif, None);
+        assert_eq!(options.apng.as_deref(), Some("b.png"));
+        assert_eq!(options.pattern.as_deref(), Some("glider.rle"));
+        let (options, effective) =
+            parse_from::<Options>(["gol_img", "--config", config, "--seed-image", "a.png"])
+                .unwrap();
+        assert_eq!(options.pattern, None);
+        assert_eq!(options.gif.as_deref(), Some("a.gif"));
+        assert!(!effective.contains_key("pattern"));
+        let (options, _) =
+            parse_from::<Options>(["gol_img", "--config", config, "--seeder", "square"]).unwrap();
+        assert_eq!(options.pattern, None);
+        fs::write(&path, "width = 8\nseeder = \"blobs\"").unwrap();
+        let (options, _) =
+            parse_from::<Options>(["gol_img", "--config", config, "--pattern", "a.rle"]).unwrap();
+        assert_eq!(options.seeder, "uniform");
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn dumps_effective_configuration() {
+        let path = write_config("dump", CONFIG);
+        let config = path.to_str().unwrap();
+        let (_, effective) =
+            parse_from::<Options>(["gol_img", "--config", config, "--output", "a.png"]).unwrap();
+        let dumped = effective.to_string();
+        let table: Table = dumped.parse().unwrap();
+        assert_eq!(table["width"], Value::Integer(64));
+        assert_eq!(table["fill"], Value::Float(0.3));
+        assert_eq!(table["fsync"], Value::Boolean(false));
+        assert_eq!(table["output"], Value::from("a.png"));
+        assert_eq!(table["seed"], Value::from("18446744073709551615"));
+        assert!(!table.contains_key("config") && !table.contains_key("help"));
+        let reloaded = write_config("reloaded", &dumped);
+        let (options, _) =
+            parse_from::<Options>(["gol_img", "--config", reloaded.to_str().unwrap()]).unwrap();
+        assert_eq!(options.output, "a.png");
+        assert_eq!(options.seed, Some(u64::MAX));
+        fs::remove_file(path).unwrap();
+        fs::remove_file(reloaded).unwrap();
+    }
+
+    #[test]
+    fn rejects_invalid_configs() {
+        for (name, content, profile) in [
+            ("unknown", "height = 3", None),
+            ("reserved", "print-config = true", None),
+            ("array", "width = [1, 2]", None),
+            ("profile", "width = 3", Some("day")),
+        ] {
+            let path = write_config(name, content);
+            let mut args = vec!["gol_img", "--config", path.to_str().unwrap()];
+            args.extend(profile.iter().flat_map(|profile| ["--profile", profile]));
+            assert!(parse_from::<Options>(args).is_err(), "{name}");
+            fs::remove_file(path).unwrap();
+        }
+    }
+}
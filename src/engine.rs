@@ -0,0 +1,123 @@
+use std::mem;
+
+use anyhow::bail;
+use clap::ValueEnum;
+
+use crate::{
+    boundary::Boundary,
+    hashlife::HashLife,
+    life::{self, new_grid, Grid},
+    packed::{self, PackedGrid},
+    rule::Rule,
+};
+
+/// Simulation backend keeping its own representation of the board.
+pub trait Engine: Send {
+    /// Replaces the state of the engine with the cells of `grid`.
+    fn load(&mut self, grid: &Grid);
+    /// Advances the board by one generation.
+    fn step(&mut self);
+    /// Advances the board by `generations` generations.
+    fn advance(&mut self, generations: u64) {
+        for _ in 0..generations {
+            self.step();
+        }
+    }
+    /// Writes the current cells into `grid`.
+    fn store(&self, grid: &mut Grid);
+}
+
+/// Available simulation engines.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum EngineKind {
+    /// One cell at a time on a boolean matrix
+    Naive,
+    /// 64 cells per word with bitwise neighbour counting
+    #[default]
+    Packed,
+    /// Memoised quadtree on an unbounded plane, so without any boundary
+    Hashlife,
+}
+
+impl EngineKind {
+    /// Creates an engine simulating a `width` by `height` board, failing if
+    /// it does not support `rule` or `boundary`. Without a boundary, the
+    /// board is a torus, or an unbounded plane for [`EngineKind::Hashlife`].
+    pub fn build(
+        self,
+        width: usize,
+        height: usize,
+        rule: Rule,
+        boundary: Option<Boundary>,
+    ) -> anyhow::Result<Box<dyn Engine>> {
+        if let (EngineKind::Hashlife, Some(boundary)) = (self, boundary) {
+            let name = boundary.to_possible_value().unwrap();
+            bail!(
+                "the hashlife engine simulates an unbounded plane and does not support the {} boundary",
+                name.get_name()
+            );
+        }
+        let boundary = boundary.unwrap_or_default();
+        Ok(match self {
+            EngineKind::Naive => Box::new(NaiveEngine {
+                cur: new_grid(width, height),
+                next: new_grid(width, height),
+                rule,
+                boundary,
+            }),
+            EngineKind::Packed => Box::new(PackedEngine {
+                cur: PackedGrid::new(width, height),
+                next: PackedGrid::new(width, height),
+                rule,
+                boundary,
+            }),
+            EngineKind::Hashlife => Box::new(HashLife::new(width, height, rule)?),
+        })
+    }
+}
+
+/// Engine computing each cell separately with [`life::next_generation`].
+pub struct NaiveEngine {
+    cur: Grid,
+    next: Grid,
+    rule: Rule,
+    boundary: Boundary,
+}
+
+impl Engine for NaiveEngine {
+    fn load(&mut self, grid: &Grid) {
+        self.cur.assign(grid);
+    }
+
+    fn step(&mut self) {
+        life::next_generation(&self.cur, &mut self.next, &self.rule, self.boundary);
+        mem::swap(&mut self.cur, &mut self.next);
+    }
+
+    fn store(&self, grid: &mut Grid) {
+        grid.assign(&self.cur);
+    }
+}
+
+/// Engine computing 64 cells at once with [`packed::next_generation`].
+pub struct PackedEngine {
+    cur: PackedGrid,
+    next: PackedGrid,
+    rule: Rule,
+    boundary: Boundary,
+}
+
+impl Engine for PackedEngine {
+    fn load(&mut self, grid: &Grid) {
+        self.cur.load(grid);
+    }
+
+    fn step(&mut self) {
+        packed::next_generation(&self.cur, &mut self.next, &self.rule, self.boundary);
+        mem::swap(&mut self.cur, &mut self.next);
+    }
+
+    fn store(&self, grid: &mut Grid) {
+        self.cur.store(grid);
+    }
+}
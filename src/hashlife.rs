@@ -0,0 +1,416 @@
+use std::collections::HashMap;
+
+use anyhow::bail;
+
+use crate::{engine::Engine, life::Grid, rule::Rule};
+
+type NodeId = u32;
+
+const DEAD: NodeId = 0;
+const ALIVE: NodeId = 1;
+
+/// Number of nodes after which the cache is rebuilt from the current root.
+const MAX_NODES: usize = 1 << 22;
+
+/// Quadtree node covering `2^level` cells on each side. Leaves are the two
+/// level 0 nodes `DEAD` and `ALIVE`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct Node {
+    level: u8,
+    /// North-west, north-east, south-west and south-east quadrants.
+    children: [NodeId; 4],
+}
+
+/// HashLife engine simulating an unbounded plane, of which the board is a
+/// window centred on the origin.
+///
+/// Identical subtrees are shared and the result of advancing each node by
+/// a power of two generations is memoised, which makes both large sparse
+/// patterns and skipping many generations at once cheap.
+pub struct HashLife {
+    rule: Rule,
+    width: usize,
+    height: usize,
+    nodes: Vec<Node>,
+    population: Vec<u64>,
+    ids: HashMap<Node, NodeId>,
+    /// Centre of a node advanced by `2^j` generations, keyed by `(node, j)`.
+    results: HashMap<(NodeId, u8), NodeId>,
+    empty: Vec<NodeId>,
+    root: NodeId,
+}
+
+impl HashLife {
+    /// Creates an engine showing a `width` by `height` window of the plane,
+    /// failing for rules with B0, under which the whole plane would come to
+    /// life.
+    pub fn new(width: usize, height: usize, rule: Rule) -> anyhow::Result<Self> {
+        if rule.birth() & 1 == 1 {
+            bail!("the hashlife engine does not support rules with B0");
+        }
+        let mut hashlife = Self {
+            rule,
+            width,
+            height,
+            nodes: Vec::new(),
+            population: Vec::new(),
+            ids: HashMap::new(),
+            results: HashMap::new(),
+            empty: Vec::new(),
+            root: DEAD,
+        };
+        hashlife.clear();
+        hashlife.root = hashlife.empty(hashlife.min_level());
+        Ok(hashlife)
+    }
+
+    fn clear(&mut self) {
+        let leaf = Node {
+            level: 0,
+            children: [DEAD; 4],
+        };
+        self.nodes = vec![leaf, leaf];
+        self.population = vec![0, 1];
+        self.ids.clear();
+        self.results.clear();
+        self.empty = vec![DEAD];
+    }
+
+    /// Smallest root level covering the whole window.
+    fn min_level(&self) -> u8 {
+        let half = self.width.max(self.height).div_ceil(2).max(4);
+        (half.next_power_of_two().trailing_zeros() + 1) as u8
+    }
+
+    fn level(&self, id: NodeId) -> u8 {
+        self.nodes[id as usize].level
+    }
+
+    fn children(&self, id: NodeId) -> [NodeId; 4] {
+        self.nodes[id as usize].children
+    }
+
+    fn join(&mut self, children: [NodeId; 4]) -> NodeId {
+        let node = Node {
+            level: self.level(children[0]) + 1,
+            children,
+        };
+        if let Some(&id) = self.ids.get(&node) {
+            return id;
+        }
+        let id = self.nodes.len() as NodeId;
+        self.nodes.push(node);
+        self.population
+            .push(children.iter().map(|&c| self.population[c as usize]).sum());
+        self.ids.insert(node, id);
+        id
+    }
+
+    fn empty(&mut self, level: u8) -> NodeId {
+        while self.empty.len() <= level as usize {
+            let e = *self.empty.last().unwrap();
+            let id = self.join([e; 4]);
+            self.empty.push(id);
+        }
+        self.empty[level as usize]
+    }
+
+    /// Node one level up with `id` in its centre.
+    fn expand(&mut self, id: NodeId) -> NodeId {
+        let [nw, ne, sw, se] = self.children(id);
+        let e = self.empty(self.level(id) - 1);
+        let nw = self.join([e, e, e, nw]);
+        let ne = self.join([e, e, ne, e]);
+        let sw = self.join([e, sw, e, e]);
+        let se = self.join([se, e, e, e]);
+        self.join([nw, ne, sw, se])
+    }
+
+    /// Whether all live cells of `id` lie in its central half.
+    fn is_padded(&self, id: NodeId) -> bool {
+        let [nw, ne, sw, se] = self.children(id).map(|c| self.children(c));
+        let inner = self.population[nw[3] as usize]
+            + self.population[ne[2] as usize]
+            + self.population[sw[1] as usize]
+            + self.population[se[0] as usize];
+        inner == self.population[id as usize]
+    }
+
+    /// Nine overlapping sub-nodes of level `level - 1` of `id`, indexed by
+    /// `[y][x]`.
+    fn nine(&mut self, id: NodeId) -> [[NodeId; 3]; 3] {
+        let [nw, ne, sw, se] = self.children(id);
+        let [_, nw_ne, nw_sw, nw_se] = self.children(nw);
+        let [ne_nw, _, ne_sw, ne_se] = self.children(ne);
+        let [sw_nw, sw_ne, _, sw_se] = self.children(sw);
+        let [se_nw, se_ne, se_sw, _] = self.children(se);
+        let n = self.join([nw_ne, ne_nw, nw_se, ne_sw]);
+        let w = self.join([nw_sw, nw_se, sw_nw, sw_ne]);
+        let c = self.join([nw_se, ne_sw, sw_ne, se_nw]);
+        let e = self.join([ne_sw, ne_se, se_nw, se_ne]);
+        let s = self.join([sw_ne, se_nw, sw_se, se_sw]);
+        [[nw, n, ne], [w, c, e], [sw, s, se]]
+    }
+
+    fn centre(&mut self, id: NodeId) -> NodeId {
+        let [nw, ne, sw, se] = self.children(id);
+        self.join([
+            self.children(nw)[3],
+            self.children(ne)[2],
+            self.children(sw)[1],
+            self.children(se)[0],
+        ])
+    }
+
+    /// Centre of the level 2 node `id` after one generation, computed cell
+    /// by cell.
+    fn step_leaf(&mut self, id: NodeId) -> NodeId {
+        let mut cells = [[false; 4]; 4];
+        for (q, &child) in self.children(id).iter().enumerate() {
+            for (r, &cell) in self.children(child).iter().enumerate() {
+                cells[(q / 2) * 2 + r / 2][(q % 2) * 2 + r % 2] = cell == ALIVE;
+            }
+        }
+        let next = |x: usize, y: usize| {
+            let neighbors = (y - 1..=y + 1)
+                .flat_map(|ny| (x - 1..=x + 1).map(move |nx| (nx, ny)))
+                .filter(|&(nx, ny)| (nx, ny) != (x, y) && cells[ny][nx])
+                .count();
+            if self.rule.next(cells[y][x], neighbors as u32) {
+                ALIVE
+            } else {
+                DEAD
+            }
+        };
+        let children = [next(1, 1), next(2, 1), next(1, 2), next(2, 2)];
+        self.join(children)
+    }
+
+    /// Centre of `id` advanced by `2^j` generations, with `j <= level - 2`.
+    fn step_node(&mut self, id: NodeId, j: u8) -> NodeId {
+        let level = self.level(id);
+        debug_assert!(j + 2 <= level);
+        if self.population[id as usize] == 0 {
+            return self.empty(level - 1);
+        }
+        if let Some(&result) = self.results.get(&(id, j)) {
+            return result;
+        }
+        let result = if level == 2 {
+            self.step_leaf(id)
+        } else {
+            let full_speed = j + 2 == level;
+            let nine = self.nine(id);
+            let mut parts = [[DEAD; 3]; 3];
+            for (y, row) in nine.iter().enumerate() {
+                for (x, &node) in row.iter().enumerate() {
+                    parts[y][x] = if full_speed {
+                        self.step_node(node, j - 1)
+                    } else {
+                        self.centre(node)
+                    };
+                }
+            }
+            let mut quadrants = [DEAD; 4];
+            for (q, quadrant) in quadrants.iter_mut().enumerate() {
+                let (qx, qy) = (q % 2, q / 2);
+                let joined = self.join([
+                    parts[qy][qx],
+                    parts[qy][qx + 1],
+                    parts[qy + 1][qx],
+                    parts[qy + 1][qx + 1],
+                ]);
+                *quadrant = self.step_node(joined, if full_speed { j - 1 } else { j });
+            }
+            self.join(quadrants)
+        };
+        self.results.insert((id, j), result);
+        result
+    }
+
+    /// Builds the node of level `level` whose top-left cell is at `(x, y)`
+    /// in window coordinates.
+    fn build(&mut self, grid: &Grid, level: u8, x: i64, y: i64) -> NodeId {
+        let size = 1i64 << level;
+        let (height, width) = grid.dim();
+        if x >= width as i64 || y >= height as i64 || x + size <= 0 || y + size <= 0 {
+            return self.empty(level);
+        }
+        if level == 0 {
+            return if grid[(y as usize, x as usize)] {
+                ALIVE
+            } else {
+                DEAD
+            };
+        }
+        let half = size / 2;
+        let children = [(0, 0), (half, 0), (0, half), (half, half)]
+            .map(|(dx, dy)| self.build(grid, level - 1, x + dx, y + dy));
+        self.join(children)
+    }
+
+    fn write(&self, grid: &mut Grid, id: NodeId, x: i64, y: i64) {
+        let size = 1i64 << self.level(id);
+        let (height, width) = grid.dim();
+        if self.population[id as usize] == 0
+            || x >= width as i64
+            || y >= height as i64
+            || x + size <= 0
+            || y + size <= 0
+        {
+            return;
+        }
+        if id == ALIVE {
+            grid[(y as usize, x as usize)] = true;
+            return;
+        }
+        let half = size / 2;
+        for (&child, (dx, dy)) in
+            self.children(id)
+                .iter()
+                .zip([(0, 0), (half, 0), (0, half), (half, half)])
+        {
+            self.write(grid, child, x + dx, y + dy);
+        }
+    }
+
+    /// Window coordinates of the top-left cell of the root.
+    fn root_origin(&self) -> (i64, i64) {
+        let half = 1i64 << (self.level(self.root) - 1);
+        (self.width as i64 / 2 - half, self.height as i64 / 2 - half)
+    }
+
+    /// Copies the nodes reachable from the root into a fresh cache once it
+    /// has grown too large.
+    fn collect_garbage(&mut self) {
+        if self.nodes.len() < MAX_NODES {
+            return;
+        }
+        let nodes = std::mem::take(&mut self.nodes);
+        self.clear();
+        let mut copied = HashMap::new();
+        self.root = self.copy(&nodes, self.root, &mut copied);
+    }
+
+    fn copy(&mut self, nodes: &[Node], id: NodeId, copied: &mut HashMap<NodeId, NodeId>) -> NodeId {
+        if id <= ALIVE {
+            return id;
+        }
+        if let Some(&new) = copied.get(&id) {
+            return new;
+        }
+        let children = nodes[id as usize]
+            .children
+            .map(|child| self.copy(nodes, child, copied));
+        let new = self.join(children);
+        copied.insert(id, new);
+        new
+    }
+}
+
+impl Engine for HashLife {
+    fn load(&mut self, grid: &Grid) {
+        self.clear();
+        let level = self.min_level();
+        let half = 1i64 << (level - 1);
+        self.root = self.build(
+            grid,
+            level,
+            self.width as i64 / 2 - half,
+            self.height as i64 / 2 - half,
+        );
+    }
+
+    fn step(&mut self) {
+        self.advance(1);
+    }
+
+    fn advance(&mut self, generations: u64) {
+        for j in (0..u64::BITS as u8).filter(|j| generations >> j & 1 == 1) {
+            while self.level(self.root) < j + 2 || !self.is_padded(self.root) {
+                self.root = self.expand(self.root);
+            }
+            let expanded = self.expand(self.root);
+            self.root = self.step_node(expanded, j);
+            self.collect_garbage();
+        }
+    }
+
+    fn store(&self, grid: &mut Grid) {
+        grid.fill(false);
+        let (x, y) = self.root_origin();
+        self.write(grid, self.root, x, y);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::ValueEnum;
+    use ndarray::{s, Array2};
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::{boundary::Boundary, engine::EngineKind, life::new_grid};
+
+    #[test]
+    fn glider_travels_across_window() {
+        let mut glider = new_grid(40, 30);
+        for (x, y) in [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)] {
+            glider[(y + 5, x + 5)] = true;
+        }
+        let mut hashlife = HashLife::new(40, 30, Rule::CONWAY).unwrap();
+        hashlife.load(&glider);
+        hashlife.advance(64);
+        let mut grid = new_grid(40, 30);
+        hashlife.store(&mut grid);
+
+        let mut expected = new_grid(40, 30);
+        expected
+            .slice_mut(s![16.., 16..])
+            .assign(&glider.slice(s![..14, ..24]));
+        assert_eq!(grid, expected);
+    }
+
+    #[test]
+    fn rejects_b0_rules() {
+        assert!(HashLife::new(8, 8, "B0/S8".parse().unwrap()).is_err());
+    }
+
+    #[test]
+    fn rejects_bounded_boards() {
+        let build = |boundary| EngineKind::Hashlife.build(8, 8, Rule::CONWAY, boundary);
+        assert!(build(None).is_ok());
+        for &boundary in Boundary::value_variants() {
+            assert!(build(Some(boundary)).is_err(), "{boundary:?}");
+        }
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn matches_naive_engine_far_from_edges(
+            cells in proptest::collection::vec(any::<bool>(), 16 * 16),
+            generations in 1..20u64,
+        ) {
+            let soup = Array2::from_shape_vec((16, 16), cells).unwrap();
+            let mut hashlife = HashLife::new(16, 16, Rule::CONWAY).unwrap();
+            hashlife.load(&soup);
+            hashlife.advance(generations);
+            let mut actual = new_grid(16, 16);
+            hashlife.store(&mut actual);
+
+            // The soup cannot grow by more than one cell per generation, so a
+            // large enough board with dead edges behaves like the plane.
+            let mut board = new_grid(64, 64);
+            board.slice_mut(s![24..40, 24..40]).assign(&soup);
+            let mut naive = EngineKind::Naive
+                .build(64, 64, Rule::CONWAY, Some(Boundary::Dead))
+                .unwrap();
+            naive.load(&board);
+            naive.advance(generations);
+            naive.store(&mut board);
+            prop_assert_eq!(actual.view(), board.slice(s![24..40, 24..40]));
+        }
+    }
+}
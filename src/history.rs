@@ -0,0 +1,279 @@
+use std::{
+    collections::VecDeque,
+    fmt,
+    hash::{DefaultHasher, Hash, Hasher},
+    str::FromStr,
+};
+
+use anyhow::anyhow;
+
+use crate::{engine::Engine, life::Grid};
+
+/// Final behaviour of a board that stopped evolving.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Fate {
+    /// No live cell is left.
+    Extinct,
+    /// The board cycles through the same states, every given number of
+    /// generations, 1 being a still life.
+    Periodic(u64),
+}
+
+/// Longest period `period:max=N` accepts, bounding the states kept in
+/// [`History`].
+pub const MAX_PERIOD: u64 = 1 << 16;
+
+/// When the board is seeded again, besides every `--max-iter` generations.
+/// Parsed from `fixed`, `extinct`, `stagnant` or `period:max=N`, the last
+/// three accepting an `after=K` parameter, as in `period:max=3,after=50`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ResetPolicy {
+    /// Only after a fixed number of generations.
+    #[default]
+    Fixed,
+    /// `after` generations after the board died out.
+    Extinct {
+        /// Generations left to the board once extinct.
+        after: u64,
+    },
+    /// `after` generations after the board stopped evolving.
+    Stagnant {
+        /// Generations left to the board once stagnant.
+        after: u64,
+    },
+    /// `after` generations after the board died out or started cycling with
+    /// a period of at most `max` generations.
+    Period {
+        /// Longest period of the cycles resetting the board.
+        max: u64,
+        /// Generations left to the board once extinct or in such a cycle.
+        after: u64,
+    },
+}
+
+impl ResetPolicy {
+    /// Whether a board having met `fate` `generations` ago must be seeded
+    /// again.
+    pub fn resets(self, fate: Fate, generations: u64) -> bool {
+        match (self, fate) {
+            (ResetPolicy::Fixed, _) => false,
+            (ResetPolicy::Extinct { after }, Fate::Extinct) => generations >= after,
+            (ResetPolicy::Extinct { .. }, Fate::Periodic(_)) => false,
+            (ResetPolicy::Stagnant { after }, _) => generations >= after,
+            (ResetPolicy::Period { after, .. }, Fate::Extinct) => generations >= after,
+            (ResetPolicy::Period { max, after }, Fate::Periodic(period)) => {
+                period <= max && generations >= after
+            }
+        }
+    }
+
+    /// Longest period in generations the policy has to notice.
+    pub fn max_period(self) -> u64 {
+        match self {
+            ResetPolicy::Period { max, .. } => max,
+            _ => 0,
+        }
+    }
+}
+
+impl FromStr for ResetPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = |reason: String| anyhow!("invalid reset policy `{s}`: {reason}");
+        let (name, parameters) = s.split_once(':').unwrap_or((s, ""));
+        let (mut max, mut after) = (None, 0);
+        for parameter in parameters.split(',').filter(|p| !p.is_empty()) {
+            let (key, value) = parameter
+                .split_once('=')
+                .ok_or_else(|| err(format!("expected key=value, got `{parameter}`")))?;
+            let value = value
+                .parse()
+                .map_err(|_| err(format!("invalid number `{value}`")))?;
+            match key {
+                "max" if name == "period" => max = Some(value),
+                "after" if name != "fixed" => after = value,
+                _ => return Err(err(format!("unknown parameter `{key}`"))),
+            }
+        }
+        Ok(match name {
+            "fixed" => ResetPolicy::Fixed,
+            "extinct" => ResetPolicy::Extinct { after },
+            "stagnant" => ResetPolicy::Stagnant { after },
+            "period" => match max {
+                Some(max) if (1..=MAX_PERIOD).contains(&max) => ResetPolicy::Period { max, after },
+                Some(_) => {
+                    return Err(err(format!(
+                        "the period must be between 1 and {MAX_PERIOD}"
+                    )))
+                }
+                None => return Err(err("missing maximum period `max`".to_string())),
+            },
+            _ => {
+                return Err(err(
+                    "expected fixed, extinct, stagnant or period".to_string()
+                ))
+            }
+        })
+    }
+}
+
+impl fmt::Display for ResetPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ResetPolicy::Fixed => write!(f, "fixed"),
+            ResetPolicy::Extinct { after } => write!(f, "extinct:after={after}"),
+            ResetPolicy::Stagnant { after } => write!(f, "stagnant:after={after}"),
+            ResetPolicy::Period { max, after } => write!(f, "period:max={max},after={after}"),
+        }
+    }
+}
+
+/// Hashes of the most recent states of the board, used to notice when it
+/// comes back to an earlier state.
+#[derive(Clone, Debug)]
+pub struct History {
+    capacity: usize,
+    hashes: VecDeque<u64>,
+}
+
+impl History {
+    /// Creates a history remembering up to `capacity` states.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            hashes: VecDeque::new(),
+        }
+    }
+
+    /// Forgets every state.
+    pub fn clear(&mut self) {
+        self.hashes.clear();
+    }
+
+    /// Records `grid`, returning how many states ago it was last seen if it
+    /// is still remembered.
+    pub fn push(&mut self, grid: &Grid) -> Option<usize> {
+        let mut hasher = DefaultHasher::new();
+        grid.hash(&mut hasher);
+        let hash = hasher.finish();
+        let cycle = self
+            .hashes
+            .iter()
+            .rev()
+            .position(|&previous| previous == hash)
+            .map(|i| i + 1);
+        if self.hashes.len() == self.capacity {
+            self.hashes.pop_front();
+        }
+        self.hashes.push_back(hash);
+        cycle
+    }
+}
+
+/// Smallest number of generations, up to `max`, after which `grid` comes
+/// back to the same state, simulated by `engine` one generation at a time.
+/// Used to find the actual period of boards seen to repeat every `max`
+/// generations, which may be a multiple of it.
+pub fn period(engine: &mut dyn Engine, grid: &Grid, max: u64) -> Option<u64> {
+    let mut next = grid.clone();
+    engine.load(grid);
+    (1..=max).find(|_| {
+        engine.step();
+        engine.store(&mut next);
+        next == *grid
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{boundary::Boundary, engine::EngineKind, life::new_grid, rule::Rule};
+
+    #[test]
+    fn finds_period_of_oscillators() {
+        let mut grid = new_grid(5, 5);
+        for x in 1..4 {
+            grid[(2, x)] = true;
+        }
+        let mut next = grid.clone();
+        let mut history = History::new(8);
+        let mut cycles = Vec::new();
+        for _ in 0..4 {
+            cycles.push(history.push(&grid));
+            crate::life::next_generation(&grid, &mut next, &Rule::CONWAY, Boundary::Dead);
+            std::mem::swap(&mut grid, &mut next);
+        }
+        assert_eq!(cycles, [None, None, Some(2), Some(2)]);
+        history.clear();
+        assert_eq!(history.push(&grid), None);
+    }
+
+    #[test]
+    fn finds_period_within_steps() {
+        let mut engine = EngineKind::Packed
+            .build(6, 6, Rule::CONWAY, Some(Boundary::Dead))
+            .unwrap();
+        let mut block = new_grid(6, 6);
+        for (x, y) in [(1, 1), (2, 1), (1, 2), (2, 2)] {
+            block[(y, x)] = true;
+        }
+        // With a step of 3 images, a still life repeats after 1 image and a
+        // blinker after 2, i.e. 3 and 6 generations.
+        assert_eq!(period(engine.as_mut(), &block, 3), Some(1));
+        let mut blinker = new_grid(6, 6);
+        for x in 1..4 {
+            blinker[(2, x)] = true;
+        }
+        assert_eq!(period(engine.as_mut(), &blinker, 6), Some(2));
+        assert_eq!(period(engine.as_mut(), &blinker, 1), None);
+    }
+
+    #[test]
+    fn parses_reset_policies() {
+        assert_eq!("fixed".parse::<ResetPolicy>().unwrap(), ResetPolicy::Fixed);
+        assert_eq!(
+            "extinct".parse::<ResetPolicy>().unwrap(),
+            ResetPolicy::Extinct { after: 0 }
+        );
+        let policy: ResetPolicy = "period:max=3,after=50".parse().unwrap();
+        assert_eq!(policy, ResetPolicy::Period { max: 3, after: 50 });
+        assert_eq!(policy.to_string().parse::<ResetPolicy>().unwrap(), policy);
+        for invalid in [
+            "period",
+            "period:max=0",
+            "period:max=65537",
+            "period:max=100000000000000",
+            "fixed:after=2",
+            "stagnant:max=2",
+            "soon",
+        ] {
+            assert!(invalid.parse::<ResetPolicy>().is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn resets_on_matching_fates() {
+        let period = ResetPolicy::Period { max: 2, after: 10 };
+        assert!(!period.resets(Fate::Periodic(2), 9));
+        assert!(period.resets(Fate::Periodic(2), 10));
+        assert!(period.resets(Fate::Extinct, 10));
+        assert!(!period.resets(Fate::Periodic(3), 100));
+        let extinct = ResetPolicy::Extinct { after: 0 };
+        assert!(extinct.resets(Fate::Extinct, 0));
+        assert!(!extinct.resets(Fate::Periodic(1), 100));
+        assert!(ResetPolicy::Stagnant { after: 0 }.resets(Fate::Periodic(15), 0));
+        assert!(!ResetPolicy::Fixed.resets(Fate::Extinct, 100));
+    }
+
+    #[test]
+    fn forgets_oldest_states() {
+        let mut history = History::new(2);
+        let grids: Vec<_> = (1..4).map(|width| new_grid(width, 1)).collect();
+        for grid in &grids {
+            assert_eq!(history.push(grid), None);
+        }
+        assert_eq!(history.push(&grids[0]), None);
+        assert_eq!(history.push(&grids[2]), Some(2));
+    }
+}
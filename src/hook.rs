@@ -0,0 +1,461 @@
+use std::{
+    fmt,
+    path::Path,
+    process::{Command, Stdio},
+    str::FromStr,
+    time::Duration,
+};
+
+use anyhow::{anyhow, bail, Context};
+use clap::ValueEnum;
+use tokio::{
+    io::{AsyncBufReadExt, BufReader},
+    sync::mpsc,
+    task::JoinHandle,
+};
+
+/// Values substituted into the `{output}`, `{generation}`, `{epoch}` and
+/// `{population}` placeholders of a [`HookCommand`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Variables {
+    /// Path of the last generated image.
+    pub output: String,
+    /// Generations computed since the start of the run.
+    pub generation: u64,
+    /// Number of the board since the start of the run, from 1.
+    pub epoch: u64,
+    /// Number of live cells.
+    pub population: usize,
+}
+
+impl Variables {
+    pub fn new(output: &Path, generation: u64, epoch: u64, population: usize) -> Self {
+        Self {
+            output: output.display().to_string(),
+            generation,
+            epoch,
+            population,
+        }
+    }
+
+    fn env(&self) -> [(&'static str, String); 4] {
+        [
+            ("GOL_OUTPUT", self.output.clone()),
+            ("GOL_GENERATION", self.generation.to_string()),
+            ("GOL_EPOCH", self.epoch.to_string()),
+            ("GOL_POPULATION", self.population.to_string()),
+        ]
+    }
+
+    fn substitute(&self, text: &str, quote: bool) -> String {
+        let output = if quote {
+            shell_words::quote(&self.output).into_owned()
+        } else {
+            self.output.clone()
+        };
+        text.replace("{output}", &output)
+            .replace("{generation}", &self.generation.to_string())
+            .replace("{epoch}", &self.epoch.to_string())
+            .replace("{population}", &self.population.to_string())
+    }
+}
+
+/// Command line split into a program and its arguments following the quoting
+/// rules of POSIX shells, without any other shell feature.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HookCommand {
+    source: String,
+    words: Vec<String>,
+}
+
+impl HookCommand {
+    /// Command running the hook with the placeholders replaced by
+    /// `variables`. With `shell`, the whole command line is run by the system
+    /// shell, the substituted path being quoted.
+    pub fn command(&self, variables: &Variables, shell: bool) -> Command {
+        if shell {
+            let line = variables.substitute(&self.source, true);
+            let mut command = if cfg!(windows) {
+                let mut command = Command::new("cmd");
+                command.arg("/C");
+                command
+            } else {
+                let mut command = Command::new("sh");
+                command.arg("-c");
+                command
+            };
+            command.arg(line);
+            return command;
+        }
+        let mut words = self
+            .words
+            .iter()
+            .map(|word| variables.substitute(word, false));
+        let mut command = Command::new(words.next().unwrap());
+        command.args(words);
+        command
+    }
+}
+
+impl FromStr for HookCommand {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let words = shell_words::split(s).map_err(|err| anyhow!("invalid command `{s}`: {err}"))?;
+        if words.is_empty() {
+            return Err(anyhow!("the command is empty"));
+        }
+        Ok(Self {
+            source: s.to_string(),
+            words,
+        })
+    }
+}
+
+impl fmt::Display for HookCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+/// What to do with a hook run while the previous one is still going.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Overlap {
+    /// Do not run the new hook
+    #[default]
+    Skip,
+    /// Run the new hook once the previous ones have finished
+    Queue,
+    /// Kill the previous hook and run the new one
+    Kill,
+}
+
+/// What to do when a hook fails to start, exits with an error or times out.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Failure {
+    /// Log the failure and go on
+    #[default]
+    Continue,
+    /// Run the hook again, up to --hook-retries times
+    Retry,
+    /// Stop the program
+    Abort,
+}
+
+/// How hooks are run.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Policy {
+    /// Duration after which a hook is killed and considered failed.
+    pub timeout: Option<Duration>,
+    pub overlap: Overlap,
+    pub failure: Failure,
+    /// Number of additional runs of a failed hook with [`Failure::Retry`].
+    pub retries: u32,
+}
+
+/// Occasion on which hooks are run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// An image has been generated.
+    Frame,
+    /// The board has been seeded again.
+    Reset,
+    /// The last live cell died.
+    Extinction,
+    /// The board came back to an earlier state.
+    Stable,
+    /// The program is stopping.
+    Exit,
+}
+
+impl Event {
+    pub fn name(self) -> &'static str {
+        match self {
+            Event::Frame => "frame",
+            Event::Reset => "reset",
+            Event::Extinction => "extinction",
+            Event::Stable => "stable",
+            Event::Exit => "exit",
+        }
+    }
+}
+
+/// Commands run in the background on events, following the same policy.
+/// Besides the placeholders, the details of the event are passed to them in
+/// the `GOL_EVENT`, `GOL_OUTPUT`, `GOL_GENERATION`, `GOL_EPOCH` and
+/// `GOL_POPULATION` environment variables, along with event specific ones.
+pub struct Hooks {
+    policy: Policy,
+    shell: bool,
+    hooks: Vec<(Event, HookCommand, HookRunner)>,
+    failures: (
+        mpsc::UnboundedSender<anyhow::Error>,
+        mpsc::UnboundedReceiver<anyhow::Error>,
+    ),
+}
+
+impl Hooks {
+    /// Creates an empty set of hooks, run by the system shell if `shell` is
+    /// set.
+    pub fn new(policy: Policy, shell: bool) -> Self {
+        Self {
+            policy,
+            shell,
+            hooks: Vec::new(),
+            failures: mpsc::unbounded_channel(),
+        }
+    }
+
+    /// Runs `command` on each `event`.
+    pub fn add(&mut self, event: Event, command: HookCommand) {
+        let name = match event {
+            Event::Frame => "command".to_string(),
+            _ => format!("on-{}", event.name()),
+        };
+        let runner = HookRunner::new(name, self.policy, self.failures.0.clone());
+        self.hooks.push((event, command, runner));
+    }
+
+    /// Runs the hooks of `event`, `details` being additional environment
+    /// variables.
+    pub fn fire(&mut self, event: Event, variables: &Variables, details: &[(&str, String)]) {
+        for (_, command, runner) in self.hooks.iter_mut().filter(|hook| hook.0 == event) {
+            let mut command = command.command(variables, self.shell);
+            command
+                .env("GOL_EVENT", event.name())
+                .envs(variables.env())
+                .envs(details.iter().map(|(name, value)| (name, value)));
+            runner.run(command);
+        }
+    }
+
+    /// Resolves with the failure of a hook whose failure policy is to abort.
+    pub async fn failed(&mut self) -> anyhow::Error {
+        match self.failures.1.recv().await {
+            Some(err) => err,
+            None => std::future::pending().await,
+        }
+    }
+
+    /// Waits for the running and queued hooks to finish.
+    pub async fn finish(&mut self) {
+        for (_, _, runner) in &mut self.hooks {
+            if let Some(running) = runner.running.take() {
+                let _ = running.await;
+            }
+        }
+    }
+}
+
+/// Runs a hook in the background, logging the lines it writes to its
+/// standard error.
+struct HookRunner {
+    name: String,
+    policy: Policy,
+    running: Option<JoinHandle<()>>,
+    failures: mpsc::UnboundedSender<anyhow::Error>,
+}
+
+impl HookRunner {
+    /// Creates a runner of the hook called `name` in the logs.
+    fn new(name: String, policy: Policy, failures: mpsc::UnboundedSender<anyhow::Error>) -> Self {
+        Self {
+            name,
+            policy,
+            running: None,
+            failures,
+        }
+    }
+
+    /// Starts running `command` according to the overlap policy.
+    fn run(&mut self, command: Command) {
+        let previous = match self.running.take() {
+            Some(running) if !running.is_finished() => match self.policy.overlap {
+                Overlap::Skip => {
+                    eprintln!("{}: still running, skipped", self.name);
+                    self.running = Some(running);
+                    return;
+                }
+                Overlap::Queue => Some(running),
+                Overlap::Kill => {
+                    running.abort();
+                    None
+                }
+            },
+            _ => None,
+        };
+        let (name, policy) = (self.name.clone(), self.policy);
+        let failures = self.failures.clone();
+        let mut command = tokio::process::Command::from(command);
+        command
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        self.running = Some(tokio::spawn(async move {
+            if let Some(previous) = previous {
+                let _ = previous.await;
+            }
+            let attempts = match policy.failure {
+                Failure::Retry => policy.retries + 1,
+                _ => 1,
+            };
+            for attempt in 1..=attempts {
+                let err = match execute(&name, &mut command, policy.timeout).await {
+                    Ok(()) => return,
+                    Err(err) => err.context(format!("{name} failed")),
+                };
+                eprintln!("{err:#}");
+                if policy.failure == Failure::Abort {
+                    let _ = failures.send(err);
+                } else if attempt < attempts {
+                    eprintln!("{name}: retrying ({attempt}/{})", policy.retries);
+                }
+            }
+        }));
+    }
+}
+
+async fn execute(
+    name: &str,
+    command: &mut tokio::process::Command,
+    timeout: Option<Duration>,
+) -> anyhow::Result<()> {
+    let mut child = command.spawn().context("failed to start")?;
+    let stderr = child.stderr.take().unwrap();
+    let log = async {
+        let mut lines = BufReader::new(stderr).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            eprintln!("{name}: {line}");
+        }
+    };
+    let run = async {
+        let (status, ()) = tokio::join!(child.wait(), log);
+        status
+    };
+    let status = match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, run)
+            .await
+            .map_err(|_| anyhow!("timed out after {} ms", timeout.as_millis()))?,
+        None => run.await,
+    }?;
+    if !status.success() {
+        bail!("{status}");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variables() -> Variables {
+        Variables::new(Path::new("my frames/gen 7.png"), 7, 2, 42)
+    }
+
+    fn arguments(command: &Command) -> Vec<String> {
+        std::iter::once(command.get_program())
+            .chain(command.get_args())
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn splits_and_substitutes_arguments() {
+        let hook: HookCommand =
+            r#"feh --bg-fill "{output}" --title 'gen {generation}/{epoch}: {population}'"#
+                .parse()
+                .unwrap();
+        assert_eq!(
+            arguments(&hook.command(&variables(), false)),
+            [
+                "feh",
+                "--bg-fill",
+                "my frames/gen 7.png",
+                "--title",
+                "gen 7/2: 42"
+            ]
+        );
+    }
+
+    #[test]
+    fn quotes_output_in_shell_mode() {
+        let hook: HookCommand = "cp {output} /tmp && echo {population}".parse().unwrap();
+        let command = hook.command(&variables(), true);
+        assert_eq!(
+            arguments(&command).last().unwrap(),
+            "cp 'my frames/gen 7.png' /tmp && echo 42"
+        );
+    }
+
+    #[test]
+    fn rejects_unbalanced_quotes() {
+        assert!("feh 'output.png".parse::<HookCommand>().is_err());
+        assert!("  ".parse::<HookCommand>().is_err());
+    }
+
+    fn hooks(failure: Failure, command: &str) -> Hooks {
+        let policy = Policy {
+            timeout: Some(Duration::from_millis(500)),
+            overlap: Overlap::Queue,
+            failure,
+            retries: 2,
+        };
+        let mut hooks = Hooks::new(policy, true);
+        hooks.add(Event::Frame, command.parse().unwrap());
+        hooks
+    }
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("gol_img_{name}_{}", std::process::id()))
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn aborts_on_failure_or_timeout() {
+        let mut hooks = hooks(
+            Failure::Abort,
+            "if [ {generation} = 7 ]; then exit 3; fi; sleep 5",
+        );
+        hooks.fire(Event::Frame, &variables(), &[]);
+        assert!(format!("{:#}", hooks.failed().await).contains("exit status: 3"));
+        let mut variables = variables();
+        variables.generation = 8;
+        hooks.fire(Event::Frame, &variables, &[]);
+        assert!(format!("{:#}", hooks.failed().await).contains("timed out"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn retries_failed_hooks() {
+        let path = temp_path("retry");
+        let mut hooks = hooks(
+            Failure::Retry,
+            &format!("echo >> {}; exit 1", path.display()),
+        );
+        hooks.fire(Event::Frame, &variables(), &[]);
+        hooks.finish().await;
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 3);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn passes_event_details_in_environment() {
+        let path = temp_path("event");
+        let mut hooks = hooks(Failure::Continue, "true");
+        let command = format!(
+            "echo $GOL_EVENT $GOL_PERIOD $GOL_GENERATION $GOL_POPULATION > {}",
+            path.display()
+        );
+        hooks.add(Event::Stable, command.parse().unwrap());
+        hooks.fire(
+            Event::Stable,
+            &variables(),
+            &[("GOL_PERIOD", "2".to_string())],
+        );
+        hooks.finish().await;
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "stable 2 7 42\n");
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,69 @@
+//! Game of life simulation rendered to images.
+//!
+//! Boards are [`Grid`]s of booleans indexed as `grid[(y, x)]`. They are
+//! seeded randomly with a [`Seeder`], from a [`Pattern`] file or from a
+//! picture, advanced by an [`Engine`] following a life-like [`Rule`] and
+//! drawn by a [`Renderer`], which colours the cells according to their
+//! [`Ages`].
+//!
+//! ```
+//! use gol_img::{Ages, Boundary, EngineKind, Pattern, Renderer, Rule};
+//!
+//! let glider = Pattern::parse_rle("x = 3, y = 3\nbo$2bo$3o!")?;
+//! let mut grid = gol_img::new_grid(16, 16);
+//! glider.place(&mut grid, (0, 0), true);
+//!
+//! let mut engine = EngineKind::Packed.build(16, 16, Rule::CONWAY, Some(Boundary::Torus))?;
+//! let mut ages = Ages::new(16, 16);
+//! engine.load(&grid);
+//! ages.reset(&grid);
+//! engine.advance(4);
+//! engine.store(&mut grid);
+//! ages.update(&grid, 4);
+//!
+//! let image = Renderer::default().render(&grid, &ages);
+//! assert_eq!(image.dimensions(), (16 * 10, 16 * 10));
+//! # anyhow::Ok(())
+//! ```
+
+#![warn(missing_docs)]
+
+/// Number of generations cells have been alive or dead for.
+pub mod age;
+/// Files replacing their destination only once completely written.
+pub mod atomic;
+/// Topologies of the board edges.
+pub mod boundary;
+/// Simulation backends.
+pub mod engine;
+/// HashLife engine on an unbounded plane.
+pub mod hashlife;
+/// Detection of boards that died out or started cycling.
+pub mod history;
+/// Board type and reference implementation of the rules.
+pub mod life;
+/// Boards storing 64 cells per word.
+pub mod packed;
+/// Pattern files.
+pub mod pattern;
+/// Boards seeded from pictures.
+pub mod picture;
+/// Animations and raw video streams of the rendered images.
+pub mod record;
+/// Images of the boards.
+pub mod render;
+/// Life-like rules.
+pub mod rule;
+/// Random boards.
+pub mod seeder;
+
+pub use crate::{
+    age::Ages,
+    boundary::Boundary,
+    engine::{Engine, EngineKind},
+    life::{new_grid, next_generation, Grid},
+    pattern::Pattern,
+    render::Renderer,
+    rule::Rule,
+    seeder::{SeedSequence, Seeder},
+};
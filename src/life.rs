@@ -0,0 +1,163 @@
+use ndarray::Array2;
+use rayon::iter::{ParallelBridge, ParallelIterator};
+
+use crate::{boundary::Boundary, rule::Rule};
+
+/// Game of life board, indexed as `grid[(y, x)]`: rows are the vertical axis
+/// (`height`) and columns the horizontal one (`width`).
+pub type Grid = Array2<bool>;
+
+/// Creates an empty board of `width` columns and `height` rows.
+pub fn new_grid(width: usize, height: usize) -> Grid {
+    Array2::from_elem((height, width), false)
+}
+
+fn next_state(grid: &Grid, x: usize, y: usize, rule: &Rule, boundary: Boundary) -> bool {
+    let mut live_neighbors = 0;
+    for i in -1..=1 {
+        for j in -1..=1 {
+            if i == 0 && j == 0 {
+                continue;
+            }
+            if boundary.cell(grid, x as isize + i, y as isize + j) {
+                live_neighbors += 1;
+            }
+        }
+    }
+    rule.next(grid[(y, x)], live_neighbors)
+}
+
+/// Computes the generation following `cur` into `next`, which must have the
+/// same dimensions, looking up neighbours across the edges with `boundary`.
+pub fn next_generation(cur: &Grid, next: &mut Grid, rule: &Rule, boundary: Boundary) {
+    debug_assert_eq!(cur.dim(), next.dim());
+    next.indexed_iter_mut()
+        .par_bridge()
+        .for_each(|((y, x), next_val)| {
+            *next_val = next_state(cur, x, y, rule, boundary);
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    fn grid_from(width: usize, height: usize, cells: &[(usize, usize)]) -> Grid {
+        let mut grid = new_grid(width, height);
+        for &(x, y) in cells {
+            grid[(y, x)] = true;
+        }
+        grid
+    }
+
+    fn step(grid: &Grid, rule: &Rule, generations: usize) -> Grid {
+        step_with(grid, rule, Boundary::Torus, generations)
+    }
+
+    fn step_with(grid: &Grid, rule: &Rule, boundary: Boundary, generations: usize) -> Grid {
+        let mut cur = grid.clone();
+        let mut next = grid.clone();
+        for _ in 0..generations {
+            next_generation(&cur, &mut next, rule, boundary);
+            std::mem::swap(&mut cur, &mut next);
+        }
+        cur
+    }
+
+    /// Rolls the board by `(dx, dy)` with wrap-around.
+    fn translate(grid: &Grid, dx: usize, dy: usize) -> Grid {
+        let (height, width) = grid.dim();
+        Array2::from_shape_fn(grid.dim(), |(y, x)| {
+            grid[(
+                (y + height - dy % height) % height,
+                (x + width - dx % width) % width,
+            )]
+        })
+    }
+
+    #[test]
+    fn new_grid_is_height_by_width() {
+        let grid = new_grid(192, 108);
+        assert_eq!(grid.nrows(), 108);
+        assert_eq!(grid.ncols(), 192);
+    }
+
+    #[test]
+    fn block_is_still_life() {
+        let block = grid_from(9, 5, &[(3, 2), (4, 2), (3, 3), (4, 3)]);
+        assert_eq!(step(&block, &Rule::CONWAY, 1), block);
+    }
+
+    #[test]
+    fn blinker_oscillates_on_wide_board() {
+        let horizontal = grid_from(11, 5, &[(4, 2), (5, 2), (6, 2)]);
+        let vertical = grid_from(11, 5, &[(5, 1), (5, 2), (5, 3)]);
+        assert_eq!(step(&horizontal, &Rule::CONWAY, 1), vertical);
+        assert_eq!(step(&horizontal, &Rule::CONWAY, 2), horizontal);
+    }
+
+    #[test]
+    fn blinker_wraps_across_short_axis() {
+        let vertical = grid_from(12, 4, &[(7, 3), (7, 0), (7, 1)]);
+        let horizontal = grid_from(12, 4, &[(6, 0), (7, 0), (8, 0)]);
+        assert_eq!(step(&horizontal, &Rule::CONWAY, 1), vertical);
+        assert_eq!(step(&vertical, &Rule::CONWAY, 1), horizontal);
+    }
+
+    #[test]
+    fn glider_moves_diagonally_on_tall_board() {
+        let glider = grid_from(6, 13, &[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]);
+        assert_eq!(step(&glider, &Rule::CONWAY, 4), translate(&glider, 1, 1));
+    }
+
+    #[test]
+    fn glider_circumnavigates_rectangular_torus() {
+        let (width, height) = (8, 12);
+        let glider = grid_from(width, height, &[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]);
+        // The glider travels one cell diagonally every 4 generations, so it
+        // is back home once it has wrapped a whole number of times on both
+        // axes.
+        assert_eq!(step(&glider, &Rule::CONWAY, 4 * 24), glider);
+        assert_ne!(step(&glider, &Rule::CONWAY, 4 * 12), glider);
+    }
+
+    #[test]
+    fn glider_turns_into_block_against_dead_corner() {
+        let glider = grid_from(7, 9, &[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]);
+        let block = grid_from(7, 9, &[(5, 6), (6, 6), (5, 7), (6, 7)]);
+        assert_eq!(step_with(&glider, &Rule::CONWAY, Boundary::Dead, 40), block);
+    }
+
+    fn arb_grid() -> impl Strategy<Value = Grid> {
+        (3..24usize, 3..24usize).prop_flat_map(|(width, height)| {
+            proptest::collection::vec(any::<bool>(), width * height)
+                .prop_map(move |cells| Array2::from_shape_vec((height, width), cells).unwrap())
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn step_commutes_with_transpose(grid in arb_grid()) {
+            let transposed = grid.t().to_owned();
+            prop_assert_eq!(
+                step(&grid, &Rule::CONWAY, 1).t().to_owned(),
+                step(&transposed, &Rule::CONWAY, 1)
+            );
+        }
+
+        #[test]
+        fn step_commutes_with_translation(grid in arb_grid(), dx in 0..24usize, dy in 0..24usize) {
+            prop_assert_eq!(
+                translate(&step(&grid, &Rule::CONWAY, 1), dx, dy),
+                step(&translate(&grid, dx, dy), &Rule::CONWAY, 1)
+            );
+        }
+
+        #[test]
+        fn step_preserves_dimensions(grid in arb_grid()) {
+            prop_assert_eq!(step(&grid, &Rule::CONWAY, 1).dim(), grid.dim());
+        }
+    }
+}
@@ -1,118 +1,580 @@
 use std::{
-    mem,
-    process::{Command, Stdio},
-    time::Duration,
+    io,
+    path::PathBuf,
+    time::{Duration, SystemTime},
 };
 
 use clap::Parser;
-use image::{GrayImage, Luma};
-use ndarray::Array2;
-use rand::prelude::*;
-use rand_xoshiro::Xoshiro256PlusPlus;
-use rayon::iter::{ParallelBridge, ParallelIterator};
-use tokio::time::sleep;
+use image::ImageFormat;
+use tokio::{sync::watch, time::sleep};
+
+use gol_img::{
+    age::Ages,
+    atomic,
+    boundary::Boundary,
+    engine::EngineKind,
+    history::{self, Fate, History, ResetPolicy},
+    life::{new_grid, Grid},
+    pattern::Pattern,
+    picture::{self, Dithering},
+    record::{ApngRecorder, GifRecorder, Recorder, StreamFormat, StreamRecorder, WebpRecorder},
+    render::{self, Color, Coloring, Falloff, Gradient, Palette, Renderer},
+    rule::Rule,
+    seeder::{SeedSequence, Seeder},
+};
+
+use crate::{
+    hook::{Event, Failure, HookCommand, Hooks, Overlap, Policy, Variables},
+    output::{OutputTemplate, Retention},
+};
+
+mod config;
+mod hook;
+mod output;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about)]
 struct Args {
-    /// Path of the image that will be generated
-    #[arg(short, long, default_value_t = String::from("output.png"))]
-    output: String,
+    /// Path of the image that will be generated, or - to stream the images to the standard
+    /// output without waiting between them. {gen}, {epoch} and {timestamp} are replaced by the
+    /// generation and board numbers and the Unix time in ms, optionally padded as in {gen:06}
+    #[arg(short, long, default_value = "output.png")]
+    output: OutputTemplate,
+    /// Number of most recent images kept when the output path has placeholders, older ones
+    /// being deleted
+    #[arg(long)]
+    keep: Option<usize>,
+    /// Sync the written files to the disk before moving them into place
+    #[arg(long)]
+    fsync: bool,
     /// Width of the game of life matrix
-    #[arg(short, long)]
+    #[arg(short, long, value_parser=clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
     width: usize,
     /// Height of the game of life matrix
-    #[arg(short, long)]
+    // -h belongs to --help.
+    #[arg(short = 'H', long, value_parser=clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
     height: usize,
     /// Cell size in pixels
     #[arg(short, long, value_parser=clap::value_parser!(u32).range(1..), default_value_t = 10)]
     size: u32,
-    /// Command which will be executed after generating each image
+    /// Command which will be executed after generating each image, split into arguments like a
+    /// shell would. {output}, {generation}, {epoch} and {population} are replaced by the image
+    /// path, the generation and board numbers and the number of live cells. Not available when
+    /// recording or streaming, which write no image file
+    #[arg(short, long, conflicts_with = "recording")]
+    command: Option<HookCommand>,
+    /// Command executed when the board is seeded again, with the same placeholders and the event
+    /// details in GOL_* environment variables
+    #[arg(long)]
+    on_reset: Option<HookCommand>,
+    /// Command executed when the last live cell dies
+    #[arg(long)]
+    on_extinction: Option<HookCommand>,
+    /// Command executed when the board comes back to an earlier state, its period in generations
+    /// being in GOL_PERIOD
+    #[arg(long)]
+    on_stable: Option<HookCommand>,
+    /// Command executed when the program stops, the reason being in GOL_REASON
+    #[arg(long)]
+    on_exit: Option<HookCommand>,
+    /// Run the commands with the system shell, allowing pipes and redirections
+    #[arg(long)]
+    shell: bool,
+    /// Time in ms after which a running command is killed and considered failed
+    #[arg(long, value_parser=clap::value_parser!(u64).range(1..))]
+    hook_timeout: Option<u64>,
+    /// What to do when a command is still running when it should be run again
+    #[arg(long, value_enum, default_value_t = Overlap::Skip)]
+    hook_overlap: Overlap,
+    /// What to do when a command fails or times out
+    #[arg(long, value_enum, default_value_t = Failure::Continue)]
+    hook_failure: Failure,
+    /// Number of times a failed command is run again with the retry failure policy
+    #[arg(long, default_value_t = 2)]
+    hook_retries: u32,
+    /// Number of generations before resetting the grid to its initial state, at least --step,
+    /// defaulting to 35 images (35 times --step) with the fixed reset policy and to no limit
+    /// otherwise
     #[arg(short, long)]
-    command: Option<String>,
-    /// Number of generations before resetting the grid to a random state
-    #[arg(short, long, default_value_t = 35)]
-    max_iter: usize,
-    /// Probability of a cell being alive when randomly filling the grid
-    #[arg(short, long, default_value_t = 0.15)]
+    max_iter: Option<u64>,
+    /// When to reset the grid besides every --max-iter generations: fixed, extinct (no live cell
+    /// left), stagnant (back to an earlier state) or period:max=N (cycling every N generations or
+    /// less, up to 65536), the last three accepting an after=K parameter to wait K generations
+    /// before resetting
+    #[arg(long, default_value = "fixed")]
+    reset_on: ResetPolicy,
+    /// Probability of a cell being alive when randomly filling the grid, for the uniform and noise
+    /// seeders without a density
+    #[arg(short, long, value_parser = parse_probability, default_value_t = 0.15)]
     fill: f64,
+    /// Generator of the random boards: uniform, square (soup in the middle of the board),
+    /// symmetric, noise, blobs or stripes, followed by parameters such as square:size=16,density=0.5
+    /// or symmetric:symmetry=d8 (c2, c4, d2, d4 or d8)
+    #[arg(long, default_value = "uniform", conflicts_with_all = ["pattern", "seed_image"])]
+    seeder: Seeder,
+    /// Seed of the random fills, making runs reproducible, the seed of each board being logged
+    #[arg(long)]
+    seed: Option<u64>,
     /// Delay in ms between each image generation
     #[arg(short, long, value_parser=clap::value_parser!(u64).range(1..), default_value_t = 1000)]
     delay: u64,
+    /// Life-like rule in B/S notation, e.g. B36/S23, 23/3, b3s23 or a name such as highlife,
+    /// defaulting to the rule of the pattern or B3/S23
+    #[arg(short, long)]
+    rule: Option<Rule>,
+    /// Topology of the board edges, defaulting to torus, or to an unbounded plane with the
+    /// hashlife engine which supports no other
+    #[arg(short, long, value_enum)]
+    boundary: Option<Boundary>,
+    /// Simulation engine
+    #[arg(short, long, value_enum, default_value_t = EngineKind::Packed)]
+    engine: EngineKind,
+    /// Number of generations computed between two generated images
+    #[arg(long, value_parser=clap::value_parser!(u64).range(1..=u32::MAX as u64), default_value_t = 1)]
+    step: u64,
+    /// Pattern file (RLE, plaintext .cells or Life 1.05/1.06) used as the initial state instead of
+    /// a random fill
+    #[arg(short, long, conflicts_with = "seed_image")]
+    pattern: Option<PathBuf>,
+    /// Horizontal offset of the pattern in cells
+    #[arg(long, allow_hyphen_values = true, default_value_t = 0)]
+    offset_x: isize,
+    /// Vertical offset of the pattern in cells
+    #[arg(long, allow_hyphen_values = true, default_value_t = 0)]
+    offset_y: isize,
+    /// Center the pattern on the grid, the offsets being relative to the center
+    #[arg(long)]
+    center: bool,
+    /// Pattern file (RLE, or plaintext if ending with .cells) the current grid is exported to
+    /// when receiving SIGUSR1
+    #[arg(long)]
+    export_pattern: Option<PathBuf>,
+    /// Picture, scaled to the grid, used as the initial state instead of a random fill
+    #[arg(long)]
+    seed_image: Option<PathBuf>,
+    /// Conversion of the picture grey levels to live and dead cells
+    #[arg(long, value_enum, default_value_t = Dithering::Threshold)]
+    dithering: Dithering,
+    /// Grey level between 0 and 1 under which pixels become live cells with the threshold dithering
+    #[arg(long, default_value_t = 0.5)]
+    threshold: f32,
+    /// Turn light pixels of the picture into live cells instead of dark ones
+    #[arg(long)]
+    invert: bool,
+    /// Preset of live and dead cell colours
+    #[arg(long, value_enum, default_value_t = Palette::Classic)]
+    palette: Palette,
+    /// Colour of live cells, as hexadecimal or CSS colour, overriding the palette
+    #[arg(long)]
+    alive_color: Option<Color>,
+    /// Colour of dead cells, as hexadecimal or CSS colour, overriding the palette
+    #[arg(long)]
+    dead_color: Option<Color>,
+    /// Colouring of live cells
+    #[arg(long, value_enum, default_value_t = Coloring::Flat)]
+    coloring: Coloring,
+    /// Comma separated colours of live cells from birth to old age, defaulting to white then the
+    /// live cell colour with the age colouring and to a thermal gradient with the heatmap
+    #[arg(long)]
+    gradient: Option<Gradient>,
+    /// Age in generations at which live cells reach the end of the gradient
+    #[arg(long, value_parser=clap::value_parser!(u32).range(1..), default_value_t = 32)]
+    age_span: u32,
+    /// Number of generations during which dead cells fade out, 0 disabling trails
+    #[arg(long, default_value_t = 0)]
+    decay: u32,
+    /// Fading curve of the trails of dead cells
+    #[arg(long, value_enum, default_value_t = Falloff::Linear)]
+    falloff: Falloff,
+    /// Record --frames images into a looping animated GIF instead of overwriting the output,
+    /// each shown for --delay ms
+    #[arg(long, group = "recording")]
+    gif: Option<PathBuf>,
+    /// Record --frames images into a looping animated PNG, keeping every colour
+    #[arg(long, group = "recording")]
+    apng: Option<PathBuf>,
+    /// Record --frames images into a looping lossless animated WebP, keeping every colour
+    #[arg(long, group = "recording")]
+    webp: Option<PathBuf>,
+    /// Number of images recorded into an animation, defaulting to 100, or streamed to the
+    /// standard output, defaulting to no limit
+    #[arg(long, value_parser=clap::value_parser!(u64).range(1..=u32::MAX as u64))]
+    frames: Option<u64>,
+    /// Encoding of the images streamed to the standard output
+    #[arg(long, value_enum, default_value_t = StreamFormat::Rgb24)]
+    stream_format: StreamFormat,
+    /// TOML file setting options by their long name, such as max-iter = 50, with [profile.NAME]
+    /// tables overriding them. Arguments and GOL_IMG_* environment variables, such as
+    /// GOL_IMG_MAX_ITER, take precedence over it
+    #[arg(long)]
+    config: Option<PathBuf>,
+    /// Profile of the configuration file applied over its top-level options
+    #[arg(long, requires = "config")]
+    profile: Option<String>,
+    /// Print the effective configuration as TOML and exit
+    #[arg(long)]
+    print_config: bool,
+}
+
+/// Parses a probability, between 0 and 1.
+fn parse_probability(s: &str) -> Result<f64, String> {
+    match s.parse() {
+        Ok(probability) if (0. ..=1.).contains(&probability) => Ok(probability),
+        Ok(_) => Err("must be between 0 and 1".to_string()),
+        Err(err) => Err(format!("{err}")),
+    }
 }
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    let args = Args::parse();
-    let mut cur: &mut Array2<bool> = &mut Array2::from_elem((args.width, args.height), false);
-    let mut next: &mut Array2<bool> = &mut Array2::from_elem((args.width, args.height), false);
-    let mut iter = args.max_iter;
-    loop {
-        if iter == args.max_iter {
-            cur.iter_mut().par_bridge().for_each_init(
-                || Xoshiro256PlusPlus::from_entropy(),
-                |rng, val| {
-                    *val = rng.gen_bool(args.fill);
-                },
-            );
-            iter = 0;
-        }
-        array2_to_image(&cur, args.size).save(&args.output)?;
-        if let Some(ref command) = args.command {
-            Command::new(&command)
-                .stdout(Stdio::null())
-                .spawn()?
-                .wait()?;
+    let (args, effective) = config::parse::<Args>()?;
+    if args.print_config {
+        print!("{effective}");
+        return Ok(());
+    }
+    let pattern = args.pattern.as_deref().map(Pattern::load).transpose()?;
+    let rule = args
+        .rule
+        .or_else(|| pattern.as_ref().and_then(|p| p.rule))
+        .unwrap_or_default();
+    let picture = args
+        .seed_image
+        .as_deref()
+        .map(|path| {
+            picture::load(
+                path,
+                args.width,
+                args.height,
+                args.dithering,
+                args.threshold,
+                args.invert,
+            )
+        })
+        .transpose()?;
+    let alive = args.alive_color.unwrap_or(args.palette.alive());
+    let renderer = Renderer {
+        size: args.size,
+        alive,
+        dead: args.dead_color.unwrap_or(args.palette.dead()),
+        coloring: args.coloring,
+        gradient: args
+            .gradient
+            .clone()
+            .unwrap_or_else(|| match args.coloring {
+                Coloring::Heatmap => Gradient::heat(),
+                _ => format!("white,{alive}").parse().unwrap(),
+            }),
+        age_span: args.age_span,
+        decay: args.decay,
+        falloff: args.falloff,
+    };
+    let mut grid = new_grid(args.width, args.height);
+    let mut ages = Ages::new(args.width, args.height);
+    let mut engine = args
+        .engine
+        .build(args.width, args.height, rule, args.boundary)?;
+    let mut export = ExportTrigger::new(args.export_pattern.is_some())?;
+    let mut shutdown = Shutdown::new(args.on_exit.is_some())?;
+    let mut hooks = Hooks::new(
+        Policy {
+            timeout: args.hook_timeout.map(Duration::from_millis),
+            overlap: args.hook_overlap,
+            failure: args.hook_failure,
+            retries: args.hook_retries,
+        },
+        args.shell,
+    );
+    for (event, command) in [
+        (Event::Frame, &args.command),
+        (Event::Reset, &args.on_reset),
+        (Event::Extinction, &args.on_extinction),
+        (Event::Stable, &args.on_stable),
+        (Event::Exit, &args.on_exit),
+    ] {
+        if let Some(command) = command {
+            hooks.add(event, command.clone());
         }
-        next_generation(cur, next);
-        mem::swap(&mut cur, &mut next);
-        iter += 1;
-        sleep(Duration::from_millis(args.delay)).await;
     }
-}
-
-fn next_state(grid: &Array2<bool>, x: usize, y: usize) -> bool {
-    let mut live_neighbors = 0;
-    for i in -1..=1 {
-        for j in -1..=1 {
-            if i == 0 && j == 0 {
+    if args.command.is_some() && args.output.is_stdout() {
+        anyhow::bail!("--command cannot be used when streaming the images to the standard output");
+    }
+    let animation = args.gif.is_some() || args.apng.is_some() || args.webp.is_some();
+    let limit = args
+        .frames
+        .unwrap_or(if animation { 100 } else { u64::MAX });
+    let mut recorder: Option<Box<dyn Recorder>> = if let Some(ref path) = args.gif {
+        Some(Box::new(GifRecorder::new(path, args.delay, args.fsync)?))
+    } else if let Some(ref path) = args.apng {
+        Some(Box::new(ApngRecorder::new(
+            path,
+            limit as u32,
+            args.delay,
+            args.fsync,
+        )?))
+    } else if let Some(ref path) = args.webp {
+        Some(Box::new(WebpRecorder::new(path, args.delay, args.fsync)?))
+    } else if args.output.is_stdout() {
+        Some(Box::new(StreamRecorder::new(
+            io::stdout(),
+            args.stream_format,
+            args.delay,
+        )))
+    } else {
+        None
+    };
+    let mut frames = 0;
+    let mut retention = args
+        .keep
+        .filter(|_| args.output.is_template())
+        .map(Retention::new);
+    // Fewer generations than a step would reseed the board on every image.
+    if let Some(max_iter) = args.max_iter.filter(|&max_iter| max_iter < args.step) {
+        anyhow::bail!(
+            "--max-iter {max_iter} would reset the grid before every image with --step {}",
+            args.step
+        );
+    }
+    let max_iter = match args.reset_on {
+        ResetPolicy::Fixed => Some(args.max_iter.unwrap_or(35 * args.step)),
+        _ => args.max_iter,
+    };
+    // Remembered states of the board, to notice oscillations of up to as
+    // many images.
+    let mut history =
+        History::new(256.max(args.reset_on.max_period().div_ceil(args.step) as usize));
+    // Engine finding the actual period of boards when skipping generations.
+    let mut probe = match args.step {
+        1 => None,
+        _ => Some(
+            args.engine
+                .build(args.width, args.height, rule, args.boundary)?,
+        ),
+    };
+    // Fate of the board and generation at which it was met.
+    let mut settled = None;
+    let mut output = PathBuf::new();
+    let (mut generation, mut epoch) = (0, 0);
+    let mut seeds = SeedSequence::new(args.seed.unwrap_or_else(rand::random));
+    let mut iter = 0;
+    let mut reset = true;
+    // A failed snapshot is not worth stopping the display for.
+    let export_grid = |grid: &Grid| {
+        if let Some(ref path) = args.export_pattern {
+            if let Err(err) = Pattern::from_grid(grid, rule).save(path, args.fsync) {
+                eprintln!("{err:#}");
+            }
+        }
+    };
+    let result = async {
+        let reason = 'run: loop {
+            if reset {
+                if let Some(ref pattern) = pattern {
+                    pattern.place(&mut grid, (args.offset_x, args.offset_y), args.center);
+                } else if let Some(ref picture) = picture {
+                    grid.assign(picture);
+                } else {
+                    let seed = seeds.next_seed();
+                    eprintln!("board {}: seed {seed}", epoch + 1);
+                    args.seeder.seed(&mut grid, seed, args.fill)?;
+                }
+                engine.load(&grid);
+                iter = 0;
+                epoch += 1;
+                reset = false;
+            }
+            engine.store(&mut grid);
+            if iter == 0 {
+                ages.reset(&grid);
+                history.clear();
+                settled = None;
+            } else {
+                ages.update(&grid, args.step);
+            }
+            let image = renderer.render(&grid, &ages);
+            if let Some(ref mut recorder) = recorder {
+                recorder.push(image)?;
+                frames += 1;
+            } else {
+                let timestamp = SystemTime::now()
+                    .duration_since(SystemTime::UNIX_EPOCH)?
+                    .as_millis() as u64;
+                output = args.output.path(generation, epoch, timestamp);
+                output::create_parent(&output)?;
+                let format = ImageFormat::from_path(&output)?;
+                atomic::write(&output, args.fsync, |writer| {
+                    Ok(render::encode(image, writer, format)?)
+                })?;
+                if let Some(ref mut retention) = retention {
+                    retention.record(output.clone())?;
+                }
+            }
+            let population = grid.iter().filter(|&&alive| alive).count();
+            let variables = Variables::new(&output, generation, epoch, population);
+            let cycle = history.push(&grid);
+            if iter == 0 && epoch > 1 {
+                hooks.fire(Event::Reset, &variables, &[]);
+            }
+            let fate = match cycle {
+                _ if settled.is_some() => None,
+                _ if population == 0 => Some(Fate::Extinct),
+                // Repeating after a number of images only shows that the
+                // period divides the generations they span.
+                Some(states) => {
+                    let generations = states as u64 * args.step;
+                    Some(Fate::Periodic(match probe {
+                        Some(ref mut probe) => history::period(probe.as_mut(), &grid, generations)
+                            .unwrap_or(generations),
+                        None => generations,
+                    }))
+                }
+                None => None,
+            };
+            if let Some(fate) = fate {
+                match fate {
+                    Fate::Extinct => hooks.fire(Event::Extinction, &variables, &[]),
+                    Fate::Periodic(period) => {
+                        let details = [("GOL_PERIOD", period.to_string())];
+                        hooks.fire(Event::Stable, &variables, &details);
+                    }
+                }
+                settled = Some((fate, generation));
+            }
+            if let Some(ref recorder) = recorder {
+                if frames == limit || recorder.closed() {
+                    break "finished";
+                }
+                if shutdown.requested() {
+                    break "signal";
+                }
+            } else {
+                hooks.fire(Event::Frame, &variables, &[]);
+            }
+            engine.advance(args.step);
+            iter += args.step;
+            generation += args.step;
+            reset = max_iter.is_some_and(|max_iter| iter >= max_iter)
+                || settled
+                    .is_some_and(|(fate, since)| args.reset_on.resets(fate, generation - since));
+            if recorder.is_some() {
+                // Recordings do not wait between images, only handling what
+                // happened while computing the last one.
+                tokio::select! {
+                    biased;
+                    err = hooks.failed() => return Err(err),
+                    _ = export.requested() => export_grid(&grid),
+                    _ = std::future::ready(()) => {}
+                }
                 continue;
             }
-            let nx = (x as isize + i).rem_euclid(grid.ncols() as isize) as usize;
-            let ny = (y as isize + j).rem_euclid(grid.ncols() as isize) as usize;
-            if grid[(ny, nx)] {
-                live_neighbors += 1;
+            let delay = sleep(Duration::from_millis(args.delay));
+            tokio::pin!(delay);
+            loop {
+                tokio::select! {
+                    _ = &mut delay => break,
+                    _ = shutdown.received() => break 'run "signal",
+                    err = hooks.failed() => return Err(err),
+                    _ = export.requested() => export_grid(&grid),
+                }
             }
+        };
+        if let (Some(recorder), "finished") = (recorder.take(), reason) {
+            recorder.finish()?;
         }
+        Ok(reason)
     }
-    match (grid[(y, x)], live_neighbors) {
-        (true, 2) | (_, 3) => true,
-        _ => false,
-    }
+    .await;
+    let population = grid.iter().filter(|&&alive| alive).count();
+    let variables = Variables::new(&output, generation, epoch, population);
+    let reason = result.as_ref().map_or("error", |reason| reason);
+    hooks.fire(
+        Event::Exit,
+        &variables,
+        &[("GOL_REASON", reason.to_string())],
+    );
+    hooks.finish().await;
+    result.map(|_| ())
 }
 
-fn next_generation(cur: &Array2<bool>, next: &mut Array2<bool>) {
-    next.indexed_iter_mut()
-        .par_bridge()
-        .for_each(|((y, x), next_val)| {
-            *next_val = next_state(cur, x, y);
-        });
+/// Request to stop the program, sent with SIGINT or SIGTERM, handled so that
+/// the exit hook can be run.
+struct Shutdown {
+    signal: Option<watch::Receiver<bool>>,
 }
 
-fn array2_to_image(grid: &Array2<bool>, size: u32) -> GrayImage {
-    let height: u32 = grid.ncols() as u32 * size;
-    let width: u32 = grid.nrows() as u32 * size;
+impl Shutdown {
+    fn new(enabled: bool) -> io::Result<Self> {
+        if !enabled {
+            return Ok(Self { signal: None });
+        }
+        #[cfg(unix)]
+        let mut terminate =
+            tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())?;
+        let (sender, receiver) = watch::channel(false);
+        tokio::spawn(async move {
+            #[cfg(unix)]
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = terminate.recv() => {}
+            }
+            #[cfg(not(unix))]
+            let _ = tokio::signal::ctrl_c().await;
+            let _ = sender.send(true);
+            // Stop right away on a second request.
+            let _ = tokio::signal::ctrl_c().await;
+            std::process::exit(130);
+        });
+        Ok(Self {
+            signal: Some(receiver),
+        })
+    }
 
-    let mut img = GrayImage::new(width, height);
+    /// Whether the program has been asked to stop.
+    fn requested(&self) -> bool {
+        self.signal.as_ref().is_some_and(|signal| *signal.borrow())
+    }
 
-    for ((x, y), &value) in grid.indexed_iter() {
-        let pixel_value = Luma([if value { 64 } else { 0 }]);
-        for i in 0..size {
-            for j in 0..size {
-                img.put_pixel(x as u32 * size + j, y as u32 * size + i, pixel_value);
+    /// Resolves when the program is asked to stop, never if the request is
+    /// not handled.
+    async fn received(&mut self) {
+        if let Some(ref mut signal) = self.signal {
+            if signal.wait_for(|&stop| stop).await.is_ok() {
+                return;
             }
         }
+        std::future::pending().await
+    }
+}
+
+/// On-demand request to export the grid, sent with SIGUSR1.
+struct ExportTrigger {
+    #[cfg(unix)]
+    signal: Option<tokio::signal::unix::Signal>,
+}
+
+impl ExportTrigger {
+    fn new(enabled: bool) -> io::Result<Self> {
+        #[cfg(unix)]
+        {
+            use tokio::signal::unix::{signal, SignalKind};
+            let signal = enabled
+                .then(|| signal(SignalKind::user_defined1()))
+                .transpose()?;
+            Ok(Self { signal })
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = enabled;
+            Ok(Self {})
+        }
     }
 
-    img
+    /// Resolves when an export is requested, never if exports are disabled.
+    async fn requested(&mut self) {
+        #[cfg(unix)]
+        if let Some(ref mut signal) = self.signal {
+            signal.recv().await;
+            return;
+        }
+        std::future::pending().await
+    }
 }
@@ -0,0 +1,230 @@
+use std::{
+    collections::VecDeque,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use anyhow::anyhow;
+
+/// Value substituted into an [`OutputTemplate`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Field {
+    /// Generations computed since the start of the run.
+    Gen,
+    /// Number of the board since the start of the run, from 1.
+    Epoch,
+    /// Unix time in milliseconds.
+    Timestamp,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Segment {
+    Text(String),
+    Field {
+        field: Field,
+        width: usize,
+        zero_padded: bool,
+    },
+}
+
+/// Path of the generated images, where `{gen}`, `{epoch}` and `{timestamp}`
+/// are replaced for each image. Placeholders may be padded with a width such
+/// as `{gen:6}`, with zeros if it starts with one as in `{gen:06}`, and
+/// braces are escaped by doubling them. `-` stands for the standard output.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OutputTemplate {
+    source: String,
+    segments: Vec<Segment>,
+}
+
+impl OutputTemplate {
+    pub fn is_stdout(&self) -> bool {
+        self.source == "-"
+    }
+
+    /// Whether the paths vary from one image to another.
+    pub fn is_template(&self) -> bool {
+        self.segments
+            .iter()
+            .any(|segment| matches!(segment, Segment::Field { .. }))
+    }
+
+    pub fn path(&self, gen: u64, epoch: u64, timestamp: u64) -> PathBuf {
+        let mut path = String::new();
+        for segment in &self.segments {
+            match *segment {
+                Segment::Text(ref text) => path.push_str(text),
+                Segment::Field {
+                    field,
+                    width,
+                    zero_padded,
+                } => {
+                    let value = match field {
+                        Field::Gen => gen,
+                        Field::Epoch => epoch,
+                        Field::Timestamp => timestamp,
+                    };
+                    if zero_padded {
+                        path.push_str(&format!("{value:0width$}"));
+                    } else {
+                        path.push_str(&format!("{value:width$}"));
+                    }
+                }
+            }
+        }
+        PathBuf::from(path)
+    }
+}
+
+impl FromStr for OutputTemplate {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = |reason: &str| anyhow!("invalid output template `{s}`: {reason}");
+        let mut segments = Vec::new();
+        let mut text = String::new();
+        let mut chars = s.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.as_str().starts_with('{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.as_str().starts_with('}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let rest = chars.as_str();
+                    let end = rest.find('}').ok_or_else(|| err("unclosed `{`"))?;
+                    let (name, format) = rest[..end].split_once(':').unwrap_or((&rest[..end], ""));
+                    let field = match name {
+                        "gen" => Field::Gen,
+                        "epoch" => Field::Epoch,
+                        "timestamp" => Field::Timestamp,
+                        _ => return Err(err(&format!("unknown placeholder `{name}`"))),
+                    };
+                    let width = match format {
+                        "" => 0,
+                        _ => format
+                            .parse()
+                            .map_err(|_| err(&format!("invalid width `{format}`")))?,
+                    };
+                    if !text.is_empty() {
+                        segments.push(Segment::Text(std::mem::take(&mut text)));
+                    }
+                    segments.push(Segment::Field {
+                        field,
+                        width,
+                        zero_padded: format.starts_with('0'),
+                    });
+                    chars = rest[end + 1..].chars();
+                }
+                '}' => return Err(err("unmatched `}`")),
+                _ => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            segments.push(Segment::Text(text));
+        }
+        Ok(Self {
+            source: s.to_string(),
+            segments,
+        })
+    }
+}
+
+impl fmt::Display for OutputTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+/// Creates the missing parent directories of `path`.
+pub fn create_parent(path: &Path) -> io::Result<()> {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => fs::create_dir_all(parent),
+        _ => Ok(()),
+    }
+}
+
+/// Keeps only the most recent files written during the run, deleting the
+/// older ones.
+#[derive(Clone, Debug)]
+pub struct Retention {
+    keep: usize,
+    written: VecDeque<PathBuf>,
+}
+
+impl Retention {
+    pub fn new(keep: usize) -> Self {
+        Self {
+            keep,
+            written: VecDeque::with_capacity(keep + 1),
+        }
+    }
+
+    /// Records that `path` has just been written, deleting the oldest file
+    /// if there are more than the number kept.
+    pub fn record(&mut self, path: PathBuf) -> io::Result<()> {
+        if let Some(i) = self.written.iter().position(|written| *written == path) {
+            self.written.remove(i);
+        }
+        self.written.push_back(path);
+        while self.written.len() > self.keep {
+            let oldest = self.written.pop_front().unwrap();
+            match fs::remove_file(&oldest) {
+                Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn substitutes_placeholders() {
+        let template: OutputTemplate = "frames/{epoch}/gen_{gen:06}_{timestamp:3}{{}}.png"
+            .parse()
+            .unwrap();
+        assert!(template.is_template());
+        assert_eq!(
+            template.path(42, 3, 7),
+            PathBuf::from("frames/3/gen_000042_  7{}.png")
+        );
+        let plain: OutputTemplate = "output.png".parse().unwrap();
+        assert!(!plain.is_template());
+        assert_eq!(plain.path(1, 2, 3), PathBuf::from("output.png"));
+    }
+
+    #[test]
+    fn rejects_invalid_templates() {
+        for template in ["{generation}", "{gen", "gen}", "{gen:x}"] {
+            assert!(template.parse::<OutputTemplate>().is_err(), "{template}");
+        }
+    }
+
+    #[test]
+    fn retention_deletes_oldest_files() {
+        let dir = std::env::temp_dir().join(format!("gol_img_retention_{}", std::process::id()));
+        let mut retention = Retention::new(2);
+        for i in 0..4 {
+            let path = dir.join(format!("{i}.png"));
+            create_parent(&path).unwrap();
+            fs::write(&path, []).unwrap();
+            retention.record(path).unwrap();
+        }
+        let mut files: Vec<_> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        files.sort();
+        assert_eq!(files, ["2.png", "3.png"]);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
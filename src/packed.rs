@@ -0,0 +1,256 @@
+use std::borrow::Cow;
+
+use rayon::prelude::*;
+
+use crate::{boundary::Boundary, life::Grid, rule::Rule};
+
+const BITS: usize = u64::BITS as usize;
+
+/// Board storing 64 cells per word, bit `x % 64` of word `x / 64` of each
+/// row. Bits past the width in the last word of a row are always zero.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PackedGrid {
+    width: usize,
+    height: usize,
+    stride: usize,
+    words: Vec<u64>,
+}
+
+impl PackedGrid {
+    /// Creates an empty board of `width` columns and `height` rows.
+    pub fn new(width: usize, height: usize) -> Self {
+        let stride = width.div_ceil(BITS);
+        Self {
+            width,
+            height,
+            stride,
+            words: vec![0; stride * height],
+        }
+    }
+
+    /// Copies the cells of `grid`, which must have the same dimensions.
+    pub fn load(&mut self, grid: &Grid) {
+        debug_assert_eq!(grid.dim(), (self.height, self.width));
+        self.words
+            .par_chunks_mut(self.stride)
+            .zip(grid.outer_iter().into_par_iter())
+            .for_each(|(words, row)| {
+                words.fill(0);
+                for (x, _) in row.indexed_iter().filter(|(_, &alive)| alive) {
+                    words[x / BITS] |= 1 << (x % BITS);
+                }
+            });
+    }
+
+    /// Writes the cells into `grid`, which must have the same dimensions.
+    pub fn store(&self, grid: &mut Grid) {
+        debug_assert_eq!(grid.dim(), (self.height, self.width));
+        grid.outer_iter_mut()
+            .into_par_iter()
+            .zip(self.words.par_chunks(self.stride))
+            .for_each(|(mut row, words)| {
+                for (x, cell) in row.indexed_iter_mut() {
+                    *cell = words[x / BITS] >> (x % BITS) & 1 == 1;
+                }
+            });
+    }
+
+    /// Whether the cell at `(x, y)` is alive.
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        self.words[y * self.stride + x / BITS] >> (x % BITS) & 1 == 1
+    }
+
+    fn row(&self, y: usize) -> &[u64] {
+        &self.words[y * self.stride..(y + 1) * self.stride]
+    }
+
+    /// Mask of the valid bits of the last word of a row.
+    fn last_mask(&self) -> u64 {
+        match self.width % BITS {
+            0 => !0,
+            bits => (1 << bits) - 1,
+        }
+    }
+
+    fn cell(&self, boundary: Boundary, x: isize, y: isize) -> bool {
+        match boundary.map(x, y, self.width, self.height) {
+            Some((x, y)) => self.get(x, y),
+            None => boundary == Boundary::Alive,
+        }
+    }
+
+    /// Words of the row at the possibly out of range `y`, resolved through
+    /// `boundary`.
+    fn boundary_row(&self, boundary: Boundary, y: isize) -> Cow<'_, [u64]> {
+        match boundary.map(0, y, self.width, self.height) {
+            // The first column of a row only maps to another column when
+            // crossing the edge mirrors the row.
+            Some((x, y)) if x != 0 => Cow::Owned(self.reversed_row(y)),
+            Some((_, y)) => Cow::Borrowed(self.row(y)),
+            None if boundary == Boundary::Alive => {
+                let mut row = vec![!0; self.stride];
+                row[self.stride - 1] = self.last_mask();
+                Cow::Owned(row)
+            }
+            None => Cow::Owned(vec![0; self.stride]),
+        }
+    }
+
+    fn reversed_row(&self, y: usize) -> Vec<u64> {
+        let mut row = vec![0; self.stride];
+        for x in (0..self.width).filter(|&x| self.get(x, y)) {
+            let x = self.width - 1 - x;
+            row[x / BITS] |= 1 << (x % BITS);
+        }
+        row
+    }
+}
+
+/// Row of cells along with the cells just past its left and right ends.
+struct Neighborhood<'a> {
+    words: Cow<'a, [u64]>,
+    left: bool,
+    right: bool,
+}
+
+impl Neighborhood<'_> {
+    /// Cells to the west, at and to the east of each cell of word `i`.
+    fn shifted(&self, i: usize, width: usize) -> (u64, u64, u64) {
+        let words = &self.words;
+        let last = words.len() - 1;
+        let before = if i == 0 {
+            self.left as u64
+        } else {
+            words[i - 1] >> (BITS - 1)
+        };
+        let after = if i == last {
+            (self.right as u64) << ((width - 1) % BITS)
+        } else {
+            words[i + 1] << (BITS - 1)
+        };
+        (words[i] << 1 | before, words[i], words[i] >> 1 | after)
+    }
+}
+
+fn half_add(a: u64, b: u64) -> (u64, u64) {
+    (a ^ b, a & b)
+}
+
+fn full_add(a: u64, b: u64, c: u64) -> (u64, u64) {
+    let t = a ^ b;
+    (t ^ c, a & b | t & c)
+}
+
+/// Applies `rule` to 64 cells at once from their 8 neighbour bitboards.
+fn next_word(alive: u64, neighbors: [u64; 8], rule: &Rule) -> u64 {
+    let [a, b, c, d, e, f, g, h] = neighbors;
+    let (sa, ca) = full_add(a, b, c);
+    let (sb, cb) = full_add(d, e, f);
+    let (sc, cc) = half_add(g, h);
+    let (ones, cd) = full_add(sa, sb, sc);
+    let (t, k1) = full_add(ca, cb, cc);
+    let (twos, k2) = half_add(t, cd);
+    let (fours, eights) = half_add(k1, k2);
+
+    let bit = |word: u64, set: bool| if set { word } else { !word };
+    let (birth, survival) = (rule.birth(), rule.survival());
+    let mut next = 0;
+    for n in 0..=8 {
+        let count = bit(ones, n & 1 != 0)
+            & bit(twos, n & 2 != 0)
+            & bit(fours, n & 4 != 0)
+            & bit(eights, n & 8 != 0);
+        let born = if birth >> n & 1 == 1 { !alive } else { 0 };
+        let survives = if survival >> n & 1 == 1 { alive } else { 0 };
+        next |= count & (born | survives);
+    }
+    next
+}
+
+/// Computes the generation following `cur` into `next`, which must have the
+/// same dimensions, looking up neighbours across the edges with `boundary`.
+pub fn next_generation(cur: &PackedGrid, next: &mut PackedGrid, rule: &Rule, boundary: Boundary) {
+    debug_assert_eq!((cur.width, cur.height), (next.width, next.height));
+    if cur.width == 0 || cur.height == 0 {
+        return;
+    }
+    let rows_per_chunk = cur.height.div_ceil(rayon::current_num_threads() * 4).max(1);
+    let last_mask = cur.last_mask();
+    next.words
+        .par_chunks_mut(cur.stride * rows_per_chunk)
+        .enumerate()
+        .for_each(|(chunk, rows)| {
+            for (dy, out) in rows.chunks_mut(cur.stride).enumerate() {
+                let y = (chunk * rows_per_chunk + dy) as isize;
+                let [north, here, south] = [y - 1, y, y + 1].map(|y| Neighborhood {
+                    words: cur.boundary_row(boundary, y),
+                    left: cur.cell(boundary, -1, y),
+                    right: cur.cell(boundary, cur.width as isize, y),
+                });
+                for (i, word) in out.iter_mut().enumerate() {
+                    let (nw, n, ne) = north.shifted(i, cur.width);
+                    let (w, alive, e) = here.shifted(i, cur.width);
+                    let (sw, s, se) = south.shifted(i, cur.width);
+                    *word = next_word(alive, [nw, n, ne, w, e, sw, s, se], rule);
+                }
+                out[cur.stride - 1] &= last_mask;
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::ValueEnum;
+    use ndarray::Array2;
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::life;
+
+    fn packed(grid: &Grid) -> PackedGrid {
+        let (height, width) = grid.dim();
+        let mut packed = PackedGrid::new(width, height);
+        packed.load(grid);
+        packed
+    }
+
+    fn arb_grid() -> impl Strategy<Value = Grid> {
+        (1..150usize, 1..12usize).prop_flat_map(|(width, height)| {
+            proptest::collection::vec(any::<bool>(), width * height)
+                .prop_map(move |cells| Array2::from_shape_vec((height, width), cells).unwrap())
+        })
+    }
+
+    fn arb_boundary() -> impl Strategy<Value = Boundary> {
+        proptest::sample::select(Boundary::value_variants())
+    }
+
+    proptest! {
+        #[test]
+        fn load_store_round_trips(grid in arb_grid()) {
+            let mut stored = grid.clone();
+            stored.fill(false);
+            packed(&grid).store(&mut stored);
+            prop_assert_eq!(stored, grid);
+        }
+
+        #[test]
+        fn matches_naive_engine(
+            grid in arb_grid(),
+            birth in 0..512u16,
+            survival in 0..512u16,
+            boundary in arb_boundary(),
+        ) {
+            let rule = Rule::new(birth, survival);
+            let mut expected = grid.clone();
+            life::next_generation(&grid, &mut expected, &rule, boundary);
+
+            let cur = packed(&grid);
+            let mut next = cur.clone();
+            next_generation(&cur, &mut next, &rule, boundary);
+            let mut actual = grid.clone();
+            next.store(&mut actual);
+            prop_assert_eq!(actual, expected);
+        }
+    }
+}
@@ -0,0 +1,529 @@
+use std::{fs, io::Write, path::Path};
+
+use anyhow::{anyhow, bail, Context};
+
+use crate::{atomic, life::Grid, rule::Rule};
+
+/// File formats patterns can be read from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PatternFormat {
+    /// Run length encoded, `.rle`
+    Rle,
+    /// Plaintext grid of `.` and `O`, `.cells`
+    Cells,
+    /// Blocks of `.` and `*` at given positions, `#Life 1.05`
+    Life105,
+    /// Live cell coordinates, `#Life 1.06`
+    Life106,
+}
+
+impl PatternFormat {
+    /// Detects the format from the header of `content`, falling back to the
+    /// extension of `path` and then to the shape of the first line.
+    pub fn detect(path: &Path, content: &str) -> Self {
+        let first = content.lines().next().unwrap_or_default().trim();
+        if first.starts_with("#Life 1.05") {
+            return PatternFormat::Life105;
+        }
+        if first.starts_with("#Life 1.06") {
+            return PatternFormat::Life106;
+        }
+        let extension = path.extension().and_then(|e| e.to_str());
+        match extension.map(str::to_ascii_lowercase).as_deref() {
+            Some("rle") => PatternFormat::Rle,
+            Some("cells") => PatternFormat::Cells,
+            _ if first.starts_with('!') || first.starts_with(['.', 'O']) => PatternFormat::Cells,
+            _ => PatternFormat::Rle,
+        }
+    }
+}
+
+/// Maximum line length of the RLE data lines written by [`Pattern::to_rle`].
+const RLE_LINE_LENGTH: usize = 70;
+
+/// Largest width and height of the patterns, in cells.
+const MAX_SIZE: usize = 1 << 16;
+
+/// Largest number of live cells of the patterns.
+const MAX_CELLS: usize = 1 << 24;
+
+/// Finite pattern of live cells, loaded from or saved to a pattern file.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Pattern {
+    /// Number of columns of the bounding box of the pattern.
+    pub width: usize,
+    /// Number of rows of the bounding box of the pattern.
+    pub height: usize,
+    /// Coordinates `(x, y)` of the live cells.
+    pub cells: Vec<(usize, usize)>,
+    /// Rule the pattern was designed for, if the file specifies it.
+    pub rule: Option<Rule>,
+}
+
+impl Pattern {
+    /// Loads the pattern file at `path`, in the format given by
+    /// [`PatternFormat::detect`].
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("failed to read pattern {}", path.display()))?;
+        match PatternFormat::detect(path, &content) {
+            PatternFormat::Rle => Self::parse_rle(&content),
+            PatternFormat::Cells => Self::parse_cells(&content),
+            PatternFormat::Life105 => Self::parse_life105(&content),
+            PatternFormat::Life106 => Self::parse_life106(&content),
+        }
+        .with_context(|| format!("failed to parse pattern {}", path.display()))
+    }
+
+    /// Builds a pattern from cells at arbitrary coordinates, moving them so
+    /// that the top-left live cell bounds are at the origin.
+    fn from_coordinates(cells: &[(isize, isize)], rule: Option<Rule>) -> anyhow::Result<Self> {
+        let left = cells.iter().map(|&(x, _)| x).min().unwrap_or(0);
+        let top = cells.iter().map(|&(_, y)| y).min().unwrap_or(0);
+        // The distances to the top-left corner fit in a usize even when the
+        // differences would overflow an isize.
+        let cells: Vec<_> = cells
+            .iter()
+            .map(|&(x, y)| (x.abs_diff(left), y.abs_diff(top)))
+            .collect();
+        Pattern {
+            width: cells
+                .iter()
+                .map(|&(x, _)| x.saturating_add(1))
+                .max()
+                .unwrap_or(0),
+            height: cells
+                .iter()
+                .map(|&(_, y)| y.saturating_add(1))
+                .max()
+                .unwrap_or(0),
+            cells,
+            rule,
+        }
+        .checked()
+    }
+
+    /// Fails if the pattern exceeds [`MAX_SIZE`] or [`MAX_CELLS`].
+    fn checked(self) -> anyhow::Result<Self> {
+        if self.width > MAX_SIZE || self.height > MAX_SIZE {
+            bail!("patterns are limited to {MAX_SIZE} by {MAX_SIZE} cells");
+        }
+        if self.cells.len() > MAX_CELLS {
+            bail!("patterns are limited to {MAX_CELLS} live cells");
+        }
+        Ok(self)
+    }
+
+    /// Parses a pattern in the plaintext format, where `!` starts a comment
+    /// line and each other line is a row of `.` (dead) and `O` (alive).
+    pub fn parse_cells(content: &str) -> anyhow::Result<Self> {
+        let mut pattern = Pattern::default();
+        let rows = content.lines().filter(|line| !line.starts_with('!'));
+        for (y, row) in rows.enumerate() {
+            let row = row.trim_end();
+            for (x, c) in row.chars().enumerate() {
+                match c {
+                    '.' => {}
+                    'O' | 'o' | '*' => pattern.cells.push((x, y)),
+                    c => bail!("unexpected character `{c}` on row {}", y + 1),
+                }
+            }
+            pattern.width = pattern.width.max(row.chars().count());
+            pattern.height = y + 1;
+        }
+        pattern.checked()
+    }
+
+    /// Parses a pattern in the Life 1.05 format, made of `#P x y` blocks of
+    /// `.` (dead) and `*` (alive) rows, with an optional `#R` rule line.
+    pub fn parse_life105(content: &str) -> anyhow::Result<Self> {
+        let mut cells = Vec::new();
+        let mut rule = None;
+        let (mut left, mut y) = (0, 0);
+        for line in content.lines().skip(1).map(str::trim_end) {
+            if let Some(position) = line.strip_prefix("#P") {
+                let mut coordinates = position.split_whitespace().map(str::parse::<isize>);
+                match (coordinates.next(), coordinates.next()) {
+                    (Some(Ok(x)), Some(Ok(top))) => (left, y) = (x, top),
+                    _ => bail!("malformed block position `{line}`"),
+                }
+            } else if let Some(value) = line.strip_prefix("#R") {
+                rule = Some(value.trim().parse()?);
+            } else if line.starts_with("#N") {
+                rule = Some(Rule::CONWAY);
+            } else if !line.starts_with('#') {
+                for (x, c) in line.chars().enumerate() {
+                    match c {
+                        '.' => {}
+                        '*' => cells.push((
+                            isize::try_from(x)
+                                .ok()
+                                .and_then(|x| left.checked_add(x))
+                                .ok_or_else(|| anyhow!("block too wide in `{line}`"))?,
+                            y,
+                        )),
+                        c => bail!("unexpected character `{c}` in `{line}`"),
+                    }
+                }
+                y = y
+                    .checked_add(1)
+                    .ok_or_else(|| anyhow!("block too tall after `{line}`"))?;
+            }
+        }
+        Self::from_coordinates(&cells, rule)
+    }
+
+    /// Parses a pattern in the Life 1.06 format, listing the `x y`
+    /// coordinates of each live cell.
+    pub fn parse_life106(content: &str) -> anyhow::Result<Self> {
+        let cells = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                let mut coordinates = line.split_whitespace().map(str::parse::<isize>);
+                match (coordinates.next(), coordinates.next(), coordinates.next()) {
+                    (Some(Ok(x)), Some(Ok(y)), None) => Ok((x, y)),
+                    _ => Err(anyhow!("malformed cell coordinates `{line}`")),
+                }
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        Self::from_coordinates(&cells, None)
+    }
+
+    /// Parses a pattern in the run length encoded format.
+    pub fn parse_rle(content: &str) -> anyhow::Result<Self> {
+        let mut pattern = Pattern::default();
+        let mut lines = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .skip_while(|line| line.starts_with('#'));
+        let header = lines.next().ok_or_else(|| anyhow!("missing header line"))?;
+        // The rule comes last and runs to the end of the line, as its
+        // bounded grid suffix such as `:T20,30` may hold commas.
+        let (fields, rule) = match header.split_once("rule") {
+            Some((fields, rule)) => (fields.trim_end().trim_end_matches(','), Some(rule)),
+            None => (header, None),
+        };
+        for field in fields.split(',').filter(|field| !field.trim().is_empty()) {
+            let (key, value) = field
+                .split_once('=')
+                .ok_or_else(|| anyhow!("malformed header field `{}`", field.trim()))?;
+            let value = value.trim();
+            match key.trim() {
+                "x" => pattern.width = value.parse().context("invalid pattern width")?,
+                "y" => pattern.height = value.parse().context("invalid pattern height")?,
+                key => bail!("unknown header field `{key}`"),
+            }
+        }
+        if let Some(rule) = rule {
+            let value = rule
+                .trim_start()
+                .strip_prefix('=')
+                .ok_or_else(|| anyhow!("malformed header field `rule{rule}`"))?
+                .trim();
+            // Bounded grid suffixes are not supported, only the rule itself
+            // is kept.
+            pattern.rule = Some(value.split(':').next().unwrap_or(value).parse()?);
+        }
+        if pattern.width > MAX_SIZE || pattern.height > MAX_SIZE {
+            bail!("patterns are limited to {MAX_SIZE} by {MAX_SIZE} cells");
+        }
+
+        let (mut x, mut y) = (0, 0);
+        let mut count: Option<usize> = None;
+        'data: for c in lines.flat_map(str::chars).filter(|c| !c.is_whitespace()) {
+            if let Some(digit) = c.to_digit(10) {
+                let run = count.unwrap_or(0).checked_mul(10);
+                count = run
+                    .and_then(|run| run.checked_add(digit as usize))
+                    .filter(|&run| run <= MAX_SIZE);
+                if count.is_none() {
+                    bail!("run count larger than {MAX_SIZE}");
+                }
+                continue;
+            }
+            let run = count.take().unwrap_or(1);
+            match c {
+                '!' => break 'data,
+                '$' => {
+                    y += run;
+                    x = 0;
+                }
+                'b' | '.' => x += run,
+                c if c.is_ascii_alphabetic() => {
+                    if x + run > MAX_SIZE {
+                        bail!("patterns are limited to {MAX_SIZE} by {MAX_SIZE} cells");
+                    }
+                    if pattern.cells.len() + run > MAX_CELLS {
+                        bail!("patterns are limited to {MAX_CELLS} live cells");
+                    }
+                    pattern.cells.extend((x..x + run).map(|x| (x, y)));
+                    x += run;
+                }
+                c => bail!("unexpected character `{c}` in pattern data"),
+            }
+            if x > MAX_SIZE || y >= MAX_SIZE {
+                bail!("patterns are limited to {MAX_SIZE} by {MAX_SIZE} cells");
+            }
+        }
+        // Trust the cells over a header that is too small for them.
+        for &(x, y) in &pattern.cells {
+            pattern.width = pattern.width.max(x + 1);
+            pattern.height = pattern.height.max(y + 1);
+        }
+        Ok(pattern)
+    }
+
+    /// Snapshot of the whole `grid`, running under `rule`.
+    pub fn from_grid(grid: &Grid, rule: Rule) -> Self {
+        let (height, width) = grid.dim();
+        Pattern {
+            width,
+            height,
+            cells: grid
+                .indexed_iter()
+                .filter(|(_, &alive)| alive)
+                .map(|((y, x), _)| (x, y))
+                .collect(),
+            rule: Some(rule),
+        }
+    }
+
+    /// Live cell columns of each row, in order.
+    fn rows(&self) -> Vec<Vec<usize>> {
+        let mut rows = vec![Vec::new(); self.height];
+        for &(x, y) in &self.cells {
+            rows[y].push(x);
+        }
+        for row in &mut rows {
+            row.sort_unstable();
+        }
+        rows
+    }
+
+    /// Atomically writes the pattern to `path`, as plaintext if its
+    /// extension is `.cells` and run length encoded otherwise, syncing it to
+    /// the disk if `sync` is set.
+    pub fn save(&self, path: &Path, sync: bool) -> anyhow::Result<()> {
+        let content = match path.extension().and_then(|e| e.to_str()) {
+            Some(e) if e.eq_ignore_ascii_case("cells") => self.to_cells(),
+            _ => self.to_rle(),
+        };
+        atomic::write(path, sync, |writer| {
+            Ok(writer.write_all(content.as_bytes())?)
+        })
+        .with_context(|| format!("failed to write pattern {}", path.display()))
+    }
+
+    /// Encodes the pattern in the run length encoded format.
+    pub fn to_rle(&self) -> String {
+        let mut header = format!("x = {}, y = {}", self.width, self.height);
+        if let Some(rule) = self.rule {
+            header += &format!(", rule = {rule}");
+        }
+        let mut tokens = Vec::new();
+        let mut push = |count: usize, tag: char| match count {
+            0 => {}
+            1 => tokens.push(tag.to_string()),
+            count => tokens.push(format!("{count}{tag}")),
+        };
+        let mut end_of_lines = 0;
+        for row in self.rows() {
+            if !row.is_empty() {
+                push(end_of_lines, '$');
+                end_of_lines = 0;
+                let mut x = 0;
+                for chunk in row.chunk_by(|a, b| a + 1 == *b) {
+                    push(chunk[0] - x, 'b');
+                    push(chunk.len(), 'o');
+                    x = chunk[0] + chunk.len();
+                }
+            }
+            end_of_lines += 1;
+        }
+        tokens.push("!".to_string());
+
+        let mut rle = header + "\n";
+        let mut line_length = 0;
+        for token in tokens {
+            if line_length + token.len() > RLE_LINE_LENGTH {
+                rle.push('\n');
+                line_length = 0;
+            }
+            line_length += token.len();
+            rle += &token;
+        }
+        rle.push('\n');
+        rle
+    }
+
+    /// Encodes the pattern in the plaintext format.
+    pub fn to_cells(&self) -> String {
+        let mut cells = String::new();
+        if let Some(rule) = self.rule {
+            cells += &format!("!Rule: {rule}\n");
+        }
+        for row in self.rows() {
+            let mut line = vec!['.'; row.last().map_or(0, |x| x + 1)];
+            for x in row {
+                line[x] = 'O';
+            }
+            cells.extend(line);
+            cells.push('\n');
+        }
+        cells
+    }
+
+    /// Clears `grid` and draws the pattern with its top-left corner at
+    /// `offset`, or at `offset` from the position centring it when `center`
+    /// is set. Cells falling outside of the grid are dropped.
+    pub fn place(&self, grid: &mut Grid, offset: (isize, isize), center: bool) {
+        let (height, width) = grid.dim();
+        let (mut left, mut top) = offset;
+        if center {
+            left += (width as isize - self.width as isize) / 2;
+            top += (height as isize - self.height as isize) / 2;
+        }
+        grid.fill(false);
+        for &(x, y) in &self.cells {
+            let (x, y) = (left + x as isize, top + y as isize);
+            if (0..width as isize).contains(&x) && (0..height as isize).contains(&y) {
+                grid[(y as usize, x as usize)] = true;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::life::new_grid;
+
+    #[test]
+    fn parses_rle_header_and_runs() {
+        let glider =
+            Pattern::parse_rle("#N Glider\nx = 3, y = 3, rule = B3/S23\nbo$2bo$3o!").unwrap();
+        assert_eq!((glider.width, glider.height), (3, 3));
+        assert_eq!(glider.rule, Some(Rule::CONWAY));
+        assert_eq!(glider.cells, [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]);
+    }
+
+    #[test]
+    fn ignores_bounded_grid_suffixes() {
+        let pattern = Pattern::parse_rle("x = 3, y = 3, rule = B3/S23:T20,30\n3o!").unwrap();
+        assert_eq!((pattern.width, pattern.height), (3, 3));
+        assert_eq!(pattern.rule, Some(Rule::CONWAY));
+        let pattern = Pattern::parse_rle("x = 3, y = 1, rule = 23/3:P10,10\n3o!").unwrap();
+        assert_eq!(pattern.rule, Some(Rule::CONWAY));
+    }
+
+    #[test]
+    fn parses_runs_across_lines() {
+        let pattern = Pattern::parse_rle("x = 12, y = 3\n2o10\nb$\n$12o!").unwrap();
+        assert_eq!(pattern.rule, None);
+        assert_eq!(pattern.cells.len(), 14);
+        assert_eq!(
+            pattern.cells[2..],
+            (0..12).map(|x| (x, 2)).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_rle() {
+        assert!(Pattern::parse_rle("bo$2bo$3o!").is_err());
+        assert!(Pattern::parse_rle("x = 3, y = 3, rule = B9\n3o!").is_err());
+        assert!(Pattern::parse_rle("x = 3, y = 3\n3o*!").is_err());
+        assert!(Pattern::parse_rle("x = 1, y = 1\n99999999999999999999999o!").is_err());
+        assert!(Pattern::parse_rle("x = 1, y = 1\n999999999999o!").is_err());
+        assert!(Pattern::parse_rle("x = 1, y = 1\n40000b40000o!").is_err());
+        assert!(Pattern::parse_rle("x = 99999999, y = 1\no!").is_err());
+    }
+
+    #[test]
+    fn rejects_oversized_patterns() {
+        let wide = format!("{}O\n", ".".repeat(MAX_SIZE));
+        assert!(Pattern::parse_cells(&wide).is_err());
+        let far = "#Life 1.06\n-9223372036854775808 0\n9223372036854775807 0\n";
+        assert!(Pattern::parse_life106(far).is_err());
+        assert!(Pattern::parse_life106("#Life 1.06\n0 0\n0 65536\n").is_err());
+        assert!(Pattern::parse_life106("#Life 1.06\n0 0\n0 65535\n").is_ok());
+        let edge = "#Life 1.05\n#P 9223372036854775807 0\n.*\n";
+        assert!(Pattern::parse_life105(edge).is_err());
+    }
+
+    #[test]
+    fn parses_plaintext_cells() {
+        let glider = Pattern::parse_cells("!Name: Glider\n!\n.O\n..O\nOOO\n").unwrap();
+        assert_eq!((glider.width, glider.height), (3, 3));
+        assert_eq!(glider.cells, [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]);
+    }
+
+    #[test]
+    fn parses_life_105_blocks() {
+        let content = "#Life 1.05\n#D Two blinkers\n#R 23/3\n#P -1 -1\n***\n#P -3 2\n*\n*\n*\n";
+        let pattern = Pattern::parse_life105(content).unwrap();
+        assert_eq!(pattern.rule, Some(Rule::CONWAY));
+        assert_eq!((pattern.width, pattern.height), (5, 6));
+        assert_eq!(
+            pattern.cells,
+            [(2, 0), (3, 0), (4, 0), (0, 3), (0, 4), (0, 5)]
+        );
+    }
+
+    #[test]
+    fn parses_life_106_coordinates() {
+        let glider = Pattern::parse_life106("#Life 1.06\n0 -1\n1 0\n-1 1\n0 1\n1 1\n").unwrap();
+        assert_eq!((glider.width, glider.height), (3, 3));
+        assert_eq!(glider.cells, [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]);
+        assert!(Pattern::parse_life106("#Life 1.06\n0 x\n").is_err());
+    }
+
+    #[test]
+    fn detects_format_from_header_then_extension() {
+        let detect = |path: &str, content| PatternFormat::detect(Path::new(path), content);
+        assert_eq!(detect("a.lif", "#Life 1.06\n0 0"), PatternFormat::Life106);
+        assert_eq!(detect("a.lif", "#Life 1.05\n*"), PatternFormat::Life105);
+        assert_eq!(detect("a.cells", "OO\nOO"), PatternFormat::Cells);
+        assert_eq!(detect("a.txt", "!Name: block"), PatternFormat::Cells);
+        assert_eq!(detect("a", "x = 1, y = 1\no!"), PatternFormat::Rle);
+    }
+
+    #[test]
+    fn rle_round_trips() {
+        let content = "x = 9, y = 5, rule = B36/S23\nbo$2bo5bo$3o2$8o!\n";
+        let pattern = Pattern::parse_rle(content).unwrap();
+        assert_eq!(pattern.to_rle(), content);
+    }
+
+    #[test]
+    fn rle_wraps_long_lines() {
+        let mut grid = new_grid(200, 1);
+        grid.iter_mut().step_by(2).for_each(|cell| *cell = true);
+        let rle = Pattern::from_grid(&grid, Rule::CONWAY).to_rle();
+        assert!(rle.lines().all(|line| line.len() <= RLE_LINE_LENGTH));
+        assert_eq!(Pattern::parse_rle(&rle).unwrap().cells.len(), 100);
+    }
+
+    #[test]
+    fn cells_round_trips() {
+        let pattern = Pattern::parse_rle("x = 4, y = 3\nbo$$o2bo!").unwrap();
+        let cells = pattern.to_cells();
+        assert_eq!(cells, ".O\n\nO..O\n");
+        assert_eq!(Pattern::parse_cells(&cells).unwrap(), pattern);
+    }
+
+    #[test]
+    fn places_centered_with_offset() {
+        let blinker = Pattern::parse_rle("x = 3, y = 1\n3o!").unwrap();
+        let mut grid = new_grid(7, 5);
+        blinker.place(&mut grid, (1, -1), true);
+        let live: Vec<_> = grid
+            .indexed_iter()
+            .filter(|(_, &v)| v)
+            .map(|(i, _)| i)
+            .collect();
+        assert_eq!(live, [(1, 3), (1, 4), (1, 5)]);
+    }
+}
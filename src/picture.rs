@@ -0,0 +1,166 @@
+use std::path::Path;
+
+use anyhow::Context;
+use clap::ValueEnum;
+use image::{imageops::FilterType, GrayImage};
+use ndarray::Array2;
+
+use crate::life::Grid;
+
+/// 8x8 Bayer matrix used for ordered dithering.
+const BAYER: [[u8; 8]; 8] = [
+    [0, 32, 8, 40, 2, 34, 10, 42],
+    [48, 16, 56, 24, 50, 18, 58, 26],
+    [12, 44, 4, 36, 14, 46, 6, 38],
+    [60, 28, 52, 20, 62, 30, 54, 22],
+    [3, 35, 11, 43, 1, 33, 9, 41],
+    [51, 19, 59, 27, 49, 17, 57, 25],
+    [15, 47, 7, 39, 13, 45, 5, 37],
+    [63, 31, 55, 23, 61, 29, 53, 21],
+];
+
+/// Conversion of the grey levels of a picture into live and dead cells.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Dithering {
+    /// Cells darker than --threshold are alive
+    #[default]
+    Threshold,
+    /// Threshold chosen with Otsu's method
+    Otsu,
+    /// Ordered dithering with an 8x8 Bayer matrix
+    #[value(alias = "ordered")]
+    Bayer,
+    /// Floyd–Steinberg error diffusion
+    FloydSteinberg,
+}
+
+/// Loads the picture at `path`, scales it to `width` by `height` and turns
+/// it into a grid where dark pixels are alive, or light ones if `invert` is
+/// set. `threshold` is the grey level, between 0 and 1, used by
+/// [`Dithering::Threshold`].
+pub fn load(
+    path: &Path,
+    width: usize,
+    height: usize,
+    dithering: Dithering,
+    threshold: f32,
+    invert: bool,
+) -> anyhow::Result<Grid> {
+    let picture = image::open(path)
+        .with_context(|| format!("failed to open picture {}", path.display()))?
+        .to_luma8();
+    let picture =
+        image::imageops::resize(&picture, width as u32, height as u32, FilterType::Triangle);
+    let mut grid = dither(&picture, dithering, threshold);
+    if invert {
+        grid.mapv_inplace(|alive| !alive);
+    }
+    Ok(grid)
+}
+
+fn dither(picture: &GrayImage, dithering: Dithering, threshold: f32) -> Grid {
+    let (width, height) = (picture.width() as usize, picture.height() as usize);
+    let levels = Array2::from_shape_fn((height, width), |(y, x)| {
+        picture.get_pixel(x as u32, y as u32)[0] as f32 / 255.
+    });
+    match dithering {
+        Dithering::Threshold => levels.mapv(|level| level < threshold),
+        Dithering::Otsu => {
+            let threshold = otsu_threshold(picture) as f32 / 255.;
+            levels.mapv(|level| level <= threshold)
+        }
+        Dithering::Bayer => Array2::from_shape_fn(levels.dim(), |(y, x)| {
+            levels[(y, x)] < (BAYER[y % 8][x % 8] as f32 + 0.5) / 64.
+        }),
+        Dithering::FloydSteinberg => {
+            let mut levels = levels;
+            let mut grid = Grid::from_elem(levels.dim(), false);
+            for y in 0..height {
+                for x in 0..width {
+                    let alive = levels[(y, x)] < 0.5;
+                    let error = levels[(y, x)] - if alive { 0. } else { 1. };
+                    grid[(y, x)] = alive;
+                    for (dx, dy, weight) in [(1, 0, 7.), (-1, 1, 3.), (0, 1, 5.), (1, 1, 1.)] {
+                        let (nx, ny) = (x as isize + dx, y + dy);
+                        if (0..width as isize).contains(&nx) && ny < height {
+                            levels[(ny, nx as usize)] += error * weight / 16.;
+                        }
+                    }
+                }
+            }
+            grid
+        }
+    }
+}
+
+/// Grey level maximising the variance between the pixels at or below it and
+/// the ones above it.
+fn otsu_threshold(picture: &GrayImage) -> u8 {
+    let mut histogram = [0u64; 256];
+    for pixel in picture.pixels() {
+        histogram[pixel[0] as usize] += 1;
+    }
+    let total: u64 = histogram.iter().sum();
+    let sum: f64 = histogram
+        .iter()
+        .enumerate()
+        .map(|(level, &count)| level as f64 * count as f64)
+        .sum();
+    let (mut below, mut below_sum) = (0u64, 0f64);
+    let (mut best, mut best_variance) = (0, -1f64);
+    for (level, &count) in histogram.iter().enumerate() {
+        below += count;
+        below_sum += level as f64 * count as f64;
+        let above = total - below;
+        if below == 0 || above == 0 {
+            continue;
+        }
+        let mean_below = below_sum / below as f64;
+        let mean_above = (sum - below_sum) / above as f64;
+        let variance = below as f64 * above as f64 * (mean_below - mean_above).powi(2);
+        if variance > best_variance {
+            (best, best_variance) = (level as u8, variance);
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grey(width: u32, height: u32, level: impl Fn(u32, u32) -> u8) -> GrayImage {
+        GrayImage::from_fn(width, height, |x, y| image::Luma([level(x, y)]))
+    }
+
+    fn population(grid: &Grid) -> usize {
+        grid.iter().filter(|&&alive| alive).count()
+    }
+
+    #[test]
+    fn otsu_separates_two_levels() {
+        let picture = grey(10, 10, |x, _| if x < 3 { 40 } else { 200 });
+        let threshold = otsu_threshold(&picture);
+        assert!((40..200).contains(&threshold));
+        assert_eq!(population(&dither(&picture, Dithering::Otsu, 0.)), 30);
+    }
+
+    #[test]
+    fn dithering_preserves_mean_level() {
+        let picture = grey(32, 32, |_, _| 64);
+        for dithering in [Dithering::Bayer, Dithering::FloydSteinberg] {
+            let alive = population(&dither(&picture, dithering, 0.5)) as f32 / 1024.;
+            assert!(
+                (alive - (1. - 64. / 255.)).abs() < 0.02,
+                "{dithering:?}: {alive}"
+            );
+        }
+    }
+
+    #[test]
+    fn threshold_keeps_dark_pixels() {
+        let picture = grey(4, 1, |x, _| x as u8 * 80);
+        let grid = dither(&picture, Dithering::Threshold, 0.5);
+        assert_eq!(grid.row(0).to_vec(), [true, true, false, false]);
+    }
+}
@@ -0,0 +1,473 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+use anyhow::{anyhow, Context};
+use clap::ValueEnum;
+use image::{DynamicImage, RgbaImage};
+use image_webp::{ColorType, WebPEncoder};
+
+use crate::atomic::AtomicFile;
+
+/// Destination of the frames of a recorded run.
+pub trait Recorder {
+    /// Appends a frame, all frames having the same dimensions.
+    fn push(&mut self, frame: RgbaImage) -> anyhow::Result<()>;
+    /// Completes the recording once all frames have been pushed.
+    fn finish(self: Box<Self>) -> anyhow::Result<()>;
+
+    /// Whether the destination stopped accepting frames, such as a pipe whose
+    /// reader exited.
+    fn closed(&self) -> bool {
+        false
+    }
+}
+
+fn create(path: &Path, sync: bool) -> anyhow::Result<AtomicFile> {
+    AtomicFile::create(path, sync).with_context(|| format!("failed to create {}", path.display()))
+}
+
+/// Flushes `writer` and renames the file it wrote into place.
+fn commit(writer: BufWriter<AtomicFile>) -> io::Result<()> {
+    writer
+        .into_inner()
+        .map_err(|err| err.into_error())?
+        .commit()
+}
+
+/// Looping animated GIF, each frame being quantised to its own palette.
+pub struct GifRecorder {
+    path: String,
+    /// Frame delay in hundredths of a second.
+    delay: u16,
+    encoder: Option<gif::Encoder<BufWriter<AtomicFile>>>,
+    writer: Option<BufWriter<AtomicFile>>,
+}
+
+impl GifRecorder {
+    /// Creates the GIF file at `path`, showing each frame for `delay`
+    /// milliseconds. The file is synced to the disk when finished if `sync`
+    /// is set.
+    pub fn new(path: &Path, delay: u64, sync: bool) -> anyhow::Result<Self> {
+        Ok(Self {
+            path: path.display().to_string(),
+            delay: delay.div_ceil(10).clamp(1, u16::MAX as u64) as u16,
+            encoder: None,
+            writer: Some(BufWriter::new(create(path, sync)?)),
+        })
+    }
+}
+
+impl Recorder for GifRecorder {
+    fn push(&mut self, frame: RgbaImage) -> anyhow::Result<()> {
+        let (width, height) = frame.dimensions();
+        let too_large = || anyhow!("{width}x{height} frames are too large for a GIF");
+        let width = u16::try_from(width).map_err(|_| too_large())?;
+        let height = u16::try_from(height).map_err(|_| too_large())?;
+        let encoder = match self.encoder {
+            Some(ref mut encoder) => encoder,
+            None => {
+                let writer = self.writer.take().expect("GIF writer already consumed");
+                let mut encoder = gif::Encoder::new(writer, width, height, &[])?;
+                encoder.set_repeat(gif::Repeat::Infinite)?;
+                self.encoder.insert(encoder)
+            }
+        };
+        let mut pixels = frame.into_raw();
+        let mut frame = gif::Frame::from_rgba_speed(width, height, &mut pixels, 10);
+        frame.delay = self.delay;
+        encoder
+            .write_frame(&frame)
+            .with_context(|| format!("failed to write a frame to {}", self.path))
+    }
+
+    fn finish(self: Box<Self>) -> anyhow::Result<()> {
+        let writer = match self.encoder {
+            Some(encoder) => encoder.into_inner()?,
+            None => return Err(anyhow!("no frame was recorded to {}", self.path)),
+        };
+        commit(writer).with_context(|| format!("failed to write {}", self.path))
+    }
+}
+
+/// Looping animated PNG, in true colour with alpha.
+pub struct ApngRecorder {
+    path: String,
+    frames: u32,
+    /// Frame delay as a fraction of a second.
+    delay: (u16, u16),
+    encoder: Option<png::Writer<BufWriter<File>>>,
+    /// The encoder writes through another handle on the file, as it cannot
+    /// give it back.
+    file: AtomicFile,
+}
+
+impl ApngRecorder {
+    /// Creates the APNG file at `path`, which will hold exactly `frames`
+    /// frames shown for `delay` milliseconds each. The file is synced to the
+    /// disk when finished if `sync` is set.
+    pub fn new(path: &Path, frames: u32, delay: u64, sync: bool) -> anyhow::Result<Self> {
+        let delay = match u16::try_from(delay) {
+            Ok(delay) => (delay, 1000),
+            Err(_) => (delay.div_ceil(1000).min(u16::MAX as u64) as u16, 1),
+        };
+        Ok(Self {
+            path: path.display().to_string(),
+            frames,
+            delay,
+            encoder: None,
+            file: create(path, sync)?,
+        })
+    }
+}
+
+impl Recorder for ApngRecorder {
+    fn push(&mut self, frame: RgbaImage) -> anyhow::Result<()> {
+        let encoder = match self.encoder {
+            Some(ref mut encoder) => encoder,
+            None => {
+                let writer = BufWriter::new(self.file.try_clone()?);
+                let mut encoder = png::Encoder::new(writer, frame.width(), frame.height());
+                encoder.set_color(png::ColorType::Rgba);
+                encoder.set_depth(png::BitDepth::Eight);
+                encoder.set_animated(self.frames, 0)?;
+                encoder.set_frame_delay(self.delay.0, self.delay.1)?;
+                self.encoder.insert(encoder.write_header()?)
+            }
+        };
+        encoder
+            .write_image_data(&frame)
+            .with_context(|| format!("failed to write a frame to {}", self.path))
+    }
+
+    fn finish(self: Box<Self>) -> anyhow::Result<()> {
+        let Self {
+            path,
+            encoder,
+            file,
+            ..
+        } = *self;
+        let Some(encoder) = encoder else {
+            return Err(anyhow!("no frame was recorded to {path}"));
+        };
+        encoder
+            .finish()
+            .map_err(anyhow::Error::from)
+            .and_then(|()| Ok(file.commit()?))
+            .with_context(|| format!("failed to write {path}"))
+    }
+}
+
+/// Looping animated WebP, each frame being losslessly compressed.
+pub struct WebpRecorder {
+    path: String,
+    /// Frame delay in milliseconds.
+    delay: u32,
+    writer: BufWriter<AtomicFile>,
+    started: bool,
+}
+
+impl WebpRecorder {
+    /// Creates the WebP file at `path`, showing each frame for `delay`
+    /// milliseconds. The file is synced to the disk when finished if `sync`
+    /// is set.
+    pub fn new(path: &Path, delay: u64, sync: bool) -> anyhow::Result<Self> {
+        Ok(Self {
+            path: path.display().to_string(),
+            delay: delay.min(0xff_ffff) as u32,
+            writer: BufWriter::new(create(path, sync)?),
+            started: false,
+        })
+    }
+
+    /// Writes the file header, whose size is filled in by
+    /// [`Recorder::finish`], along with the canvas and animation chunks.
+    fn start(&mut self, width: u32, height: u32) -> anyhow::Result<()> {
+        const ANIMATION: u8 = 1 << 1;
+        const ALPHA: u8 = 1 << 4;
+        self.writer.write_all(b"RIFF\0\0\0\0WEBP")?;
+        let mut vp8x = vec![ANIMATION | ALPHA, 0, 0, 0];
+        vp8x.extend(u24(width - 1));
+        vp8x.extend(u24(height - 1));
+        write_chunk(&mut self.writer, b"VP8X", &vp8x)?;
+        // Transparent background and infinite loop.
+        write_chunk(&mut self.writer, b"ANIM", &[0; 6])?;
+        Ok(())
+    }
+}
+
+impl Recorder for WebpRecorder {
+    fn push(&mut self, frame: RgbaImage) -> anyhow::Result<()> {
+        let (width, height) = frame.dimensions();
+        if width > 1 << 14 || height > 1 << 14 {
+            return Err(anyhow!("{width}x{height} frames are too large for a WebP"));
+        }
+        if !self.started {
+            self.start(width, height)
+                .with_context(|| format!("failed to write {}", self.path))?;
+            self.started = true;
+        }
+        let mut still = Vec::new();
+        WebPEncoder::new(&mut still).encode(&frame, width, height, ColorType::Rgba8)?;
+        // Frame position, dimensions and duration, then the VP8L chunk of the
+        // still image without its RIFF header. The frame replaces the canvas
+        // instead of being blended into it.
+        let mut anmf = vec![0; 6];
+        anmf.extend(u24(width - 1));
+        anmf.extend(u24(height - 1));
+        anmf.extend(u24(self.delay));
+        anmf.push(1 << 1);
+        anmf.extend_from_slice(&still[12..]);
+        write_chunk(&mut self.writer, b"ANMF", &anmf)
+            .with_context(|| format!("failed to write a frame to {}", self.path))
+    }
+
+    fn finish(self: Box<Self>) -> anyhow::Result<()> {
+        if !self.started {
+            return Err(anyhow!("no frame was recorded to {}", self.path));
+        }
+        let mut writer = self.writer;
+        let finish = || -> io::Result<()> {
+            let size = writer.stream_position()? - 8;
+            writer.seek(SeekFrom::Start(4))?;
+            writer.write_all(&(size as u32).to_le_bytes())?;
+            commit(writer)
+        };
+        finish().with_context(|| format!("failed to write {}", self.path))
+    }
+}
+
+/// Encoding of the frames streamed by a [`StreamRecorder`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum StreamFormat {
+    /// Headerless 8-bit RGB pixels, for `ffmpeg -f rawvideo -pix_fmt rgb24`
+    #[default]
+    Rgb24,
+    /// Headerless 8-bit grey levels, for `ffmpeg -f rawvideo -pix_fmt gray`
+    Gray8,
+    /// Concatenated binary PPM images
+    Ppm,
+    /// YUV4MPEG2 stream in 4:4:4 chroma with the frame rate set from the delay
+    Y4m,
+}
+
+/// Unbounded stream of frames, written as they come to a pipe or a file.
+pub struct StreamRecorder<W: Write> {
+    format: StreamFormat,
+    /// Frame delay in milliseconds.
+    delay: u64,
+    writer: BufWriter<W>,
+    started: bool,
+    closed: bool,
+}
+
+impl<W: Write> StreamRecorder<W> {
+    /// Streams to `writer` frames meant to be shown for `delay` milliseconds
+    /// each.
+    pub fn new(writer: W, format: StreamFormat, delay: u64) -> Self {
+        Self {
+            format,
+            delay,
+            writer: BufWriter::new(writer),
+            started: false,
+            closed: false,
+        }
+    }
+
+    fn write(&mut self, frame: RgbaImage) -> io::Result<()> {
+        let (width, height) = frame.dimensions();
+        let frame = DynamicImage::ImageRgba8(frame);
+        match self.format {
+            StreamFormat::Rgb24 => self.writer.write_all(&frame.into_rgb8()),
+            StreamFormat::Gray8 => self.writer.write_all(&frame.into_luma8()),
+            StreamFormat::Ppm => {
+                write!(self.writer, "P6\n{width} {height}\n255\n")?;
+                self.writer.write_all(&frame.into_rgb8())
+            }
+            StreamFormat::Y4m => {
+                if !self.started {
+                    let divisor = gcd(1000, self.delay);
+                    let (rate, scale) = (1000 / divisor, self.delay / divisor);
+                    writeln!(
+                        self.writer,
+                        "YUV4MPEG2 W{width} H{height} F{rate}:{scale} Ip A1:1 C444"
+                    )?;
+                }
+                self.writer.write_all(b"FRAME\n")?;
+                let pixels = frame.into_rgb8();
+                let planes: [fn(i32, i32, i32) -> i32; 3] = [
+                    |r, g, b| ((66 * r + 129 * g + 25 * b + 128) >> 8) + 16,
+                    |r, g, b| ((-38 * r - 74 * g + 112 * b + 128) >> 8) + 128,
+                    |r, g, b| ((112 * r - 94 * g - 18 * b + 128) >> 8) + 128,
+                ];
+                for plane in planes {
+                    let plane: Vec<u8> = pixels
+                        .pixels()
+                        .map(|p| plane(p[0] as i32, p[1] as i32, p[2] as i32) as u8)
+                        .collect();
+                    self.writer.write_all(&plane)?;
+                }
+                Ok(())
+            }
+        }?;
+        self.started = true;
+        Ok(())
+    }
+}
+
+impl<W: Write> Recorder for StreamRecorder<W> {
+    fn push(&mut self, frame: RgbaImage) -> anyhow::Result<()> {
+        if self.closed {
+            return Ok(());
+        }
+        match self.write(frame) {
+            Err(err) if err.kind() == io::ErrorKind::BrokenPipe => {
+                self.closed = true;
+                Ok(())
+            }
+            result => result.context("failed to stream a frame"),
+        }
+    }
+
+    fn finish(mut self: Box<Self>) -> anyhow::Result<()> {
+        match self.writer.flush() {
+            Err(err) if err.kind() != io::ErrorKind::BrokenPipe => {
+                Err(err).context("failed to stream a frame")
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn closed(&self) -> bool {
+        self.closed
+    }
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+fn u24(value: u32) -> [u8; 3] {
+    let [a, b, c, _] = value.to_le_bytes();
+    [a, b, c]
+}
+
+/// Writes a RIFF chunk, padded to an even size.
+fn write_chunk(writer: &mut impl Write, name: &[u8; 4], data: &[u8]) -> std::io::Result<()> {
+    writer.write_all(name)?;
+    writer.write_all(&(data.len() as u32).to_le_bytes())?;
+    writer.write_all(data)?;
+    if data.len() % 2 == 1 {
+        writer.write_all(&[0])?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, io::BufReader, path::PathBuf};
+
+    use super::*;
+
+    fn record(recorder: impl Recorder + 'static) {
+        let mut recorder: Box<dyn Recorder> = Box::new(recorder);
+        for level in [0, 128, 255] {
+            recorder
+                .push(RgbaImage::from_pixel(4, 3, image::Rgba([level, 0, 0, 255])))
+                .unwrap();
+        }
+        recorder.finish().unwrap();
+    }
+
+    fn temp_path(extension: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("gol_img_{}.{extension}", std::process::id()))
+    }
+
+    #[test]
+    fn gif_loops_over_all_frames() {
+        let path = temp_path("gif");
+        record(GifRecorder::new(&path, 250, false).unwrap());
+        let mut options = gif::DecodeOptions::new();
+        options.set_color_output(gif::ColorOutput::RGBA);
+        let mut decoder = options.read_info(File::open(&path).unwrap()).unwrap();
+        let mut delays = Vec::new();
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            assert_eq!((frame.width, frame.height), (4, 3));
+            delays.push(frame.delay);
+        }
+        assert_eq!(delays, [25; 3]);
+        assert_eq!(decoder.repeat(), gif::Repeat::Infinite);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn apng_loops_over_all_frames() {
+        let path = temp_path("png");
+        record(ApngRecorder::new(&path, 3, 250, false).unwrap());
+        let decoder = png::Decoder::new(BufReader::new(File::open(&path).unwrap()));
+        let mut reader = decoder.read_info().unwrap();
+        let animation = reader.info().animation_control.unwrap();
+        assert_eq!((animation.num_frames, animation.num_plays), (3, 0));
+        let mut pixels = vec![0; reader.output_buffer_size().unwrap()];
+        for level in [0, 128, 255] {
+            reader.next_frame(&mut pixels).unwrap();
+            assert_eq!(pixels[..4], [level, 0, 0, 255]);
+            let control = reader.info().frame_control.unwrap();
+            assert_eq!((control.delay_num, control.delay_den), (250, 1000));
+        }
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn webp_loops_over_all_frames() {
+        let path = temp_path("webp");
+        record(WebpRecorder::new(&path, 250, false).unwrap());
+        let file = BufReader::new(File::open(&path).unwrap());
+        let mut decoder = image_webp::WebPDecoder::new(file).unwrap();
+        assert!(decoder.is_animated());
+        assert_eq!(decoder.dimensions(), (4, 3));
+        assert_eq!(decoder.num_frames(), 3);
+        assert_eq!(decoder.loop_count(), image_webp::LoopCount::Forever);
+        let mut pixels = vec![0; decoder.output_buffer_size().unwrap()];
+        for level in [0, 128, 255] {
+            assert_eq!(decoder.read_frame(&mut pixels).unwrap(), 250);
+            assert_eq!(pixels[..4], [level, 0, 0, 255]);
+        }
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn y4m_stream_has_header_and_frames() {
+        let mut output = Vec::new();
+        let mut recorder = StreamRecorder::new(&mut output, StreamFormat::Y4m, 40);
+        for level in [0, 255] {
+            recorder
+                .push(RgbaImage::from_pixel(2, 1, image::Rgba([level; 4])))
+                .unwrap();
+        }
+        Box::new(recorder).finish().unwrap();
+        let header = b"YUV4MPEG2 W2 H1 F25:1 Ip A1:1 C444\n";
+        assert_eq!(output[..header.len()], header[..]);
+        let frames = &output[header.len()..];
+        assert_eq!(frames.len(), 2 * (6 + 6));
+        assert_eq!(frames[..12], *b"FRAME\n\x10\x10\x80\x80\x80\x80");
+        assert_eq!(frames[12..20], *b"FRAME\n\xeb\xeb");
+    }
+
+    #[test]
+    fn raw_streams_have_no_header() {
+        for (format, size) in [(StreamFormat::Rgb24, 18), (StreamFormat::Gray8, 6)] {
+            let mut output = Vec::new();
+            let mut recorder = StreamRecorder::new(&mut output, format, 1000);
+            recorder.push(RgbaImage::new(3, 2)).unwrap();
+            Box::new(recorder).finish().unwrap();
+            assert_eq!(output.len(), size);
+        }
+    }
+}
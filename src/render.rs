@@ -0,0 +1,379 @@
+use std::{
+    fmt,
+    io::{Seek, Write},
+    str::FromStr,
+};
+
+use anyhow::anyhow;
+use clap::ValueEnum;
+use image::{DynamicImage, ImageFormat, ImageResult, Rgba, RgbaImage};
+
+use crate::{age::Ages, life::Grid};
+
+/// RGBA colour, parsed from CSS colour syntax such as `#1e1e2e`, `#fff8`,
+/// `rgb(30 30 46)` or named colours like `teal`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Color(pub Rgba<u8>);
+
+impl Color {
+    /// Opaque colour from its red, green and blue components.
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Self(Rgba([r, g, b, 255]))
+    }
+
+    /// Linear interpolation towards `other`, `t` going from 0 to 1.
+    pub fn mix(self, other: Color, t: f32) -> Color {
+        let (Rgba(a), Rgba(b)) = (self.0, other.0);
+        Color(Rgba(std::array::from_fn(|i| {
+            (a[i] as f32 + (b[i] as f32 - a[i] as f32) * t).round() as u8
+        })))
+    }
+}
+
+impl FromStr for Color {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let color = csscolorparser::parse(s)
+            .or_else(|err| match s.len() {
+                // Also accept hexadecimal colours without the leading `#`.
+                3 | 4 | 6 | 8 if s.chars().all(|c| c.is_ascii_hexdigit()) => {
+                    csscolorparser::parse(&format!("#{s}"))
+                }
+                _ => Err(err),
+            })
+            .map_err(|err| anyhow!("invalid colour `{s}`: {err}"))?;
+        Ok(Self(Rgba(color.to_rgba8())))
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [r, g, b, a] = self.0 .0;
+        write!(f, "#{r:02x}{g:02x}{b:02x}")?;
+        if a != 255 {
+            write!(f, "{a:02x}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Colour stops evenly spread between 0 and 1, parsed from a comma separated
+/// list of colours.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Gradient(Vec<Color>);
+
+impl Gradient {
+    /// Thermal colour map going from white hot to dark purple.
+    pub fn heat() -> Self {
+        Self(vec![
+            Color::rgb(252, 255, 164),
+            Color::rgb(249, 142, 9),
+            Color::rgb(188, 55, 84),
+            Color::rgb(87, 16, 110),
+            Color::rgb(30, 12, 69),
+        ])
+    }
+
+    /// Colour at `t`, clamped between 0 and 1.
+    pub fn at(&self, t: f32) -> Color {
+        let last = self.0.len() - 1;
+        let position = t.clamp(0., 1.) * last as f32;
+        let i = (position as usize).min(last.saturating_sub(1));
+        match self.0.get(i + 1) {
+            Some(&next) => self.0[i].mix(next, position - i as f32),
+            None => self.0[i],
+        }
+    }
+}
+
+impl FromStr for Gradient {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let stops = s
+            .split(',')
+            .map(str::parse)
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        if stops.is_empty() {
+            return Err(anyhow!("a gradient needs at least one colour"));
+        }
+        Ok(Self(stops))
+    }
+}
+
+impl fmt::Display for Gradient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let stops: Vec<_> = self.0.iter().map(Color::to_string).collect();
+        write!(f, "{}", stops.join(","))
+    }
+}
+
+/// How live cells are coloured.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Coloring {
+    /// Every live cell has the live cell colour
+    #[default]
+    Flat,
+    /// Live cells go linearly through the gradient, from birth to --age-span generations
+    Age,
+    /// Live cells go through a thermal gradient on a logarithmic age scale, so that still
+    /// lifes, oscillators and births stand apart
+    Heatmap,
+}
+
+/// Shape of the fading of recently dead cells.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Falloff {
+    /// Constant fading speed
+    #[default]
+    Linear,
+    /// Fast fading at first, slowing down afterwards
+    Exponential,
+}
+
+impl Falloff {
+    /// Intensity, from 1 down to 0, of a cell dead for `generations` out of
+    /// a trail lasting `decay` generations.
+    fn intensity(self, generations: u32, decay: u32) -> f32 {
+        if generations > decay {
+            return 0.;
+        }
+        let elapsed = (generations - 1) as f32 / decay as f32;
+        match self {
+            Falloff::Linear => 1. - elapsed,
+            // Down to 1% of the intensity at the end of the trail.
+            Falloff::Exponential => (-elapsed * 100f32.ln()).exp(),
+        }
+    }
+}
+
+/// Preset pairs of live and dead cell colours.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Palette {
+    /// Dark grey on black
+    #[default]
+    Classic,
+    /// White on black
+    Mono,
+    /// Black on white
+    Paper,
+    /// Amber phosphor terminal
+    Amber,
+    /// Green phosphor terminal
+    Matrix,
+    /// Frost blue on polar night, from the Nord theme
+    Nord,
+    /// Purple on dark grey, from the Dracula theme
+    Dracula,
+    /// Yellow on dark grey, from the Gruvbox theme
+    Gruvbox,
+    /// Cyan on dark blue, from the Solarized theme
+    SolarizedDark,
+    /// Blue on cream, from the Solarized theme
+    SolarizedLight,
+}
+
+impl Palette {
+    /// Colour of live cells.
+    pub fn alive(self) -> Color {
+        match self {
+            Palette::Classic => Color::rgb(64, 64, 64),
+            Palette::Mono => Color::rgb(255, 255, 255),
+            Palette::Paper => Color::rgb(0, 0, 0),
+            Palette::Amber => Color::rgb(255, 176, 0),
+            Palette::Matrix => Color::rgb(0, 255, 65),
+            Palette::Nord => Color::rgb(136, 192, 208),
+            Palette::Dracula => Color::rgb(189, 147, 249),
+            Palette::Gruvbox => Color::rgb(250, 189, 47),
+            Palette::SolarizedDark => Color::rgb(42, 161, 152),
+            Palette::SolarizedLight => Color::rgb(38, 139, 210),
+        }
+    }
+
+    /// Colour of dead cells.
+    pub fn dead(self) -> Color {
+        match self {
+            Palette::Classic | Palette::Mono | Palette::Amber => Color::rgb(0, 0, 0),
+            Palette::Paper => Color::rgb(255, 255, 255),
+            Palette::Matrix => Color::rgb(13, 2, 8),
+            Palette::Nord => Color::rgb(46, 52, 64),
+            Palette::Dracula => Color::rgb(40, 42, 54),
+            Palette::Gruvbox => Color::rgb(40, 40, 40),
+            Palette::SolarizedDark => Color::rgb(0, 43, 54),
+            Palette::SolarizedLight => Color::rgb(253, 246, 227),
+        }
+    }
+}
+
+/// Turns grids into images, drawing each cell as a `size` pixels square.
+#[derive(Clone, Debug)]
+pub struct Renderer {
+    /// Side of the square drawn for each cell, in pixels.
+    pub size: u32,
+    /// Colour of live cells with the flat colouring, which dead cells fade
+    /// from.
+    pub alive: Color,
+    /// Colour of dead cells.
+    pub dead: Color,
+    /// Colouring of live cells.
+    pub coloring: Coloring,
+    /// Colours of live cells from birth to old age, for the age based
+    /// colourings.
+    pub gradient: Gradient,
+    /// Age in generations at which live cells reach the end of the gradient.
+    pub age_span: u32,
+    /// Number of generations dead cells take to fade from the live cell
+    /// colour to the dead one, 0 disabling trails.
+    pub decay: u32,
+    /// Fading curve of the trails of dead cells.
+    pub falloff: Falloff,
+}
+
+impl Default for Renderer {
+    /// Classic palette with flat colouring and no trails, 10 pixels per cell.
+    fn default() -> Self {
+        let palette = Palette::default();
+        Self {
+            size: 10,
+            alive: palette.alive(),
+            dead: palette.dead(),
+            coloring: Coloring::default(),
+            gradient: Gradient(vec![Color::rgb(255, 255, 255), palette.alive()]),
+            age_span: 32,
+            decay: 0,
+            falloff: Falloff::default(),
+        }
+    }
+}
+
+impl Renderer {
+    fn cell_color(&self, alive: bool, age: i32) -> Color {
+        if !alive {
+            return match age.unsigned_abs() {
+                dead if self.decay > 0 && dead <= self.decay => self
+                    .dead
+                    .mix(self.alive, self.falloff.intensity(dead, self.decay)),
+                _ => self.dead,
+            };
+        }
+        let age = age.max(1) as f32 - 1.;
+        let span = self.age_span.max(1) as f32;
+        match self.coloring {
+            Coloring::Flat => self.alive,
+            Coloring::Age => self.gradient.at(age / span),
+            Coloring::Heatmap => self.gradient.at(age.ln_1p() / span.ln_1p()),
+        }
+    }
+
+    /// Draws `grid`, colouring its cells according to their `ages`.
+    pub fn render(&self, grid: &Grid, ages: &Ages) -> RgbaImage {
+        let (height, width) = grid.dim();
+        RgbaImage::from_fn(
+            width as u32 * self.size,
+            height as u32 * self.size,
+            |x, y| {
+                let (x, y) = ((x / self.size) as usize, (y / self.size) as usize);
+                self.cell_color(grid[(y, x)], ages.get(x, y)).0
+            },
+        )
+    }
+}
+
+/// Encodes `image` to `writer` as `format`, converting it first to the
+/// pixel type of the formats that cannot store 8-bit RGBA, such as JPEG.
+pub fn encode(
+    image: RgbaImage,
+    writer: &mut (impl Write + Seek),
+    format: ImageFormat,
+) -> ImageResult<()> {
+    let image = DynamicImage::ImageRgba8(image);
+    match format {
+        ImageFormat::Jpeg | ImageFormat::Pnm => image.into_rgb8().write_to(writer, format),
+        ImageFormat::Farbfeld => image.into_rgba16().write_to(writer, format),
+        ImageFormat::Hdr => image.into_rgb32f().write_to(writer, format),
+        ImageFormat::OpenExr => image.into_rgba32f().write_to(writer, format),
+        _ => image.write_to(writer, format),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::life::new_grid;
+
+    #[test]
+    fn encodes_formats_without_alpha() {
+        let mut grid = new_grid(4, 3);
+        grid[(1, 2)] = true;
+        let image = Renderer::default().render(&grid, &Ages::new(4, 3));
+        for format in [ImageFormat::Jpeg, ImageFormat::Pnm, ImageFormat::Png] {
+            let mut buffer = Cursor::new(Vec::new());
+            encode(image.clone(), &mut buffer, format).unwrap();
+            let decoded = image::load_from_memory_with_format(buffer.get_ref(), format).unwrap();
+            assert_eq!((decoded.width(), decoded.height()), (40, 30), "{format:?}");
+        }
+    }
+
+    #[test]
+    fn parses_hex_and_css_colors() {
+        assert_eq!("#ff8000".parse::<Color>().unwrap(), Color::rgb(255, 128, 0));
+        assert_eq!("ff8000".parse::<Color>().unwrap(), Color::rgb(255, 128, 0));
+        assert_eq!("teal".parse::<Color>().unwrap(), Color::rgb(0, 128, 128));
+        assert_eq!("#0008".parse::<Color>().unwrap().0, Rgba([0, 0, 0, 136]));
+        assert!("notacolor".parse::<Color>().is_err());
+    }
+
+    #[test]
+    fn displays_as_parsable_hex() {
+        for color in ["#1e1e2e", "#ff000080"] {
+            assert_eq!(color.parse::<Color>().unwrap().to_string(), color);
+        }
+    }
+
+    #[test]
+    fn gradient_interpolates_between_stops() {
+        let gradient: Gradient = "black,white,#ff0000".parse().unwrap();
+        assert_eq!(gradient.at(0.), Color::rgb(0, 0, 0));
+        assert_eq!(gradient.at(0.25), Color::rgb(128, 128, 128));
+        assert_eq!(gradient.at(0.75), Color::rgb(255, 128, 128));
+        assert_eq!(gradient.at(2.), Color::rgb(255, 0, 0));
+        let single: Gradient = "teal".parse().unwrap();
+        assert_eq!(single.at(0.5), Color::rgb(0, 128, 128));
+    }
+
+    #[test]
+    fn trails_fade_out_over_decay() {
+        for falloff in [Falloff::Linear, Falloff::Exponential] {
+            assert_eq!(falloff.intensity(1, 10), 1.);
+            assert!(falloff.intensity(5, 10) > falloff.intensity(6, 10));
+            assert!(falloff.intensity(10, 10) > 0.);
+            assert_eq!(falloff.intensity(11, 10), 0.);
+        }
+        assert!(Falloff::Exponential.intensity(3, 10) < Falloff::Linear.intensity(3, 10));
+    }
+
+    #[test]
+    fn renders_rectangular_grid() {
+        let mut grid = new_grid(3, 2);
+        grid[(1, 2)] = true;
+        let renderer = Renderer {
+            size: 2,
+            alive: Palette::Mono.alive(),
+            dead: Palette::Mono.dead(),
+            coloring: Coloring::Flat,
+            gradient: Gradient::heat(),
+            age_span: 32,
+            decay: 0,
+            falloff: Falloff::Linear,
+        };
+        let mut ages = Ages::new(3, 2);
+        ages.reset(&grid);
+        let image = renderer.render(&grid, &ages);
+        assert_eq!(image.dimensions(), (6, 4));
+        assert_eq!(*image.get_pixel(5, 3), Palette::Mono.alive().0);
+        assert_eq!(*image.get_pixel(3, 3), Palette::Mono.dead().0);
+    }
+}
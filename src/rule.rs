@@ -0,0 +1,203 @@
+use std::{fmt, str::FromStr};
+
+use anyhow::{anyhow, bail};
+
+/// Life-like cellular automaton rule, stored as bitmasks of the neighbour
+/// counts (0 to 8) that cause a birth or allow a cell to survive.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Rule {
+    birth: u16,
+    survival: u16,
+}
+
+impl Rule {
+    /// Conway's Game of Life, B3/S23.
+    pub const CONWAY: Rule = Rule::new(0b1000, 0b1100);
+
+    const NAMED: [(&'static str, &'static str); 8] = [
+        ("life", "B3/S23"),
+        ("conway", "B3/S23"),
+        ("highlife", "B36/S23"),
+        ("daynight", "B3678/S34678"),
+        ("seeds", "B2/S"),
+        ("maze", "B3/S12345"),
+        ("lifewithoutdeath", "B3/S012345678"),
+        ("replicator", "B1357/S1357"),
+    ];
+
+    /// Builds a rule from birth and survival masks, where bit `n` is set when
+    /// `n` live neighbours trigger the transition.
+    pub const fn new(birth: u16, survival: u16) -> Self {
+        Self {
+            birth: birth & 0x1ff,
+            survival: survival & 0x1ff,
+        }
+    }
+
+    /// Mask of the neighbour counts giving birth to a dead cell.
+    pub fn birth(&self) -> u16 {
+        self.birth
+    }
+
+    /// Mask of the neighbour counts keeping a live cell alive.
+    pub fn survival(&self) -> u16 {
+        self.survival
+    }
+
+    /// State of a cell in the next generation given its current state and
+    /// its number of live neighbours.
+    pub fn next(&self, alive: bool, neighbors: u32) -> bool {
+        let mask = if alive { self.survival } else { self.birth };
+        mask >> neighbors & 1 == 1
+    }
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Self::CONWAY
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = |mask: u16| -> String {
+            (0..=8)
+                .filter(|n| mask >> n & 1 == 1)
+                .map(|n| char::from(b'0' + n as u8))
+                .collect()
+        };
+        write!(f, "B{}/S{}", digits(self.birth), digits(self.survival))
+    }
+}
+
+impl FromStr for Rule {
+    type Err = anyhow::Error;
+
+    /// Parses `B36/S23`, `b36s23` and the older survival-first `23/36`
+    /// notation, as well as a few well known rule names such as `highlife`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rule = s.trim().to_ascii_lowercase();
+        let name: String = rule.chars().filter(char::is_ascii_alphanumeric).collect();
+        if let Some((_, rule)) = Self::NAMED.iter().find(|(n, _)| *n == name) {
+            return rule.parse();
+        }
+        let (birth, survival) = if rule.starts_with(['b', 's']) {
+            parse_bs(&rule)
+        } else {
+            parse_sb(&rule)
+        }
+        .map_err(|err| anyhow!("invalid rulestring `{s}`: {err}"))?;
+        Ok(Rule::new(birth, survival))
+    }
+}
+
+/// Parses the `B`/`S` prefixed notation, with or without a slash between
+/// the two parts and in either order.
+fn parse_bs(rule: &str) -> anyhow::Result<(u16, u16)> {
+    let (first, second) = match rule.split_once('/') {
+        Some(parts) => parts,
+        None => rule.split_at(rule[1..].find(['b', 's']).map_or(rule.len(), |i| i + 1)),
+    };
+    let (mut birth, mut survival) = (None, None);
+    for part in [first, second] {
+        let slot = match part.chars().next() {
+            Some('b') => &mut birth,
+            Some('s') => &mut survival,
+            Some(c) => bail!("expected `B` or `S`, found `{c}`"),
+            // Reported below as a missing part.
+            None => continue,
+        };
+        if slot.is_some() {
+            bail!("`{}` appears twice", part[..1].to_ascii_uppercase());
+        }
+        *slot = Some(parse_counts(&part[1..])?);
+    }
+    Ok((
+        birth.ok_or_else(|| anyhow!("missing birth (`B`) part"))?,
+        survival.ok_or_else(|| anyhow!("missing survival (`S`) part"))?,
+    ))
+}
+
+/// Parses the survival-first `<survival>/<birth>` notation.
+fn parse_sb(rule: &str) -> anyhow::Result<(u16, u16)> {
+    let (survival, birth) = rule
+        .split_once('/')
+        .ok_or_else(|| anyhow!("expected `B<digits>/S<digits>` or `<digits>/<digits>`"))?;
+    Ok((parse_counts(birth)?, parse_counts(survival)?))
+}
+
+fn parse_counts(digits: &str) -> anyhow::Result<u16> {
+    let mut mask = 0;
+    for c in digits.chars() {
+        let n = match c.to_digit(10) {
+            Some(n @ 0..=8) => n,
+            Some(n) => bail!("neighbour count {n} is out of range 0-8"),
+            None => bail!("unexpected character `{c}`"),
+        };
+        if mask >> n & 1 == 1 {
+            bail!("neighbour count {n} is repeated");
+        }
+        mask |= 1 << n;
+    }
+    Ok(mask)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(s: &str) -> Rule {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_notations() {
+        let highlife = Rule::new(0b1001000, 0b1100);
+        assert_eq!(rule("B36/S23"), highlife);
+        assert_eq!(rule("b3s23"), Rule::CONWAY);
+        assert_eq!(rule("S23B3"), Rule::CONWAY);
+        assert_eq!(rule("S23/B36"), highlife);
+        assert_eq!(rule("23/36"), highlife);
+        assert_eq!(rule("B2/S"), Rule::new(0b100, 0));
+    }
+
+    #[test]
+    fn parses_named_rules() {
+        assert_eq!(rule("life"), Rule::CONWAY);
+        assert_eq!(rule("Conway"), Rule::CONWAY);
+        assert_eq!(rule("highlife"), rule("B36/S23"));
+        assert_eq!(rule("Day & Night"), rule("B3678/S34678"));
+        assert_eq!(rule("seeds"), rule("B2/S"));
+        assert_eq!(rule("life without death"), rule("B3/S012345678"));
+        for (name, _) in Rule::NAMED {
+            assert!(name.parse::<Rule>().is_ok(), "{name}");
+        }
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for s in ["B3/S23", "B36/S23", "B2/S", "B/S012345678", "B1357/S1357"] {
+            assert_eq!(rule(s).to_string(), s);
+            assert_eq!(rule(&rule(s).to_string()), rule(s));
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_rules() {
+        for (s, reason) in [
+            ("B9", "out of range"),
+            ("B33/S2", "repeated"),
+            ("B3/B3", "appears twice"),
+            ("x3", "expected"),
+            ("", "expected"),
+            ("B3", "missing survival"),
+        ] {
+            let err = s.parse::<Rule>().unwrap_err().to_string();
+            assert!(
+                err.starts_with(&format!("invalid rulestring `{s}`")),
+                "{err}"
+            );
+            assert!(err.contains(reason), "{s}: {err}");
+        }
+    }
+}
@@ -0,0 +1,728 @@
+use std::{fmt, str::FromStr};
+
+use anyhow::anyhow;
+use clap::ValueEnum;
+use rand::prelude::*;
+use rand_xoshiro::Xoshiro256PlusPlus;
+use rayon::prelude::*;
+
+use crate::life::Grid;
+
+/// Number of cells filled from each random stream.
+const CHUNK: usize = 4096;
+
+/// Seeds of the successive boards of a run, the first one being the seed of
+/// the run itself so that any board can be reproduced by starting a run with
+/// its seed.
+#[derive(Clone, Debug)]
+pub struct SeedSequence {
+    next: u64,
+}
+
+impl SeedSequence {
+    /// Starts the sequence with the seed of the run.
+    pub fn new(seed: u64) -> Self {
+        Self { next: seed }
+    }
+
+    /// Seed of the next board.
+    pub fn next_seed(&mut self) -> u64 {
+        let seed = self.next;
+        self.next = split_mix(seed);
+        seed
+    }
+}
+
+/// SplitMix64 output function, a bijection of 64-bit integers mixing its
+/// input thoroughly.
+fn split_mix(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Randomly fills `grid`, each cell being alive with probability `density`.
+pub fn random_fill(grid: &mut Grid, density: f64, seed: u64) {
+    fill_with(grid, seed, |_, _| density);
+}
+
+/// Randomly fills `grid`, the cell at `(x, y)` being alive with probability
+/// `probability(x, y)`. The grid is split into fixed chunks, each with its
+/// own random stream derived from `seed`, so that the result depends on the
+/// seed only, and not on how the chunks are spread over threads.
+fn fill_with(grid: &mut Grid, seed: u64, probability: impl Fn(usize, usize) -> f64 + Sync) {
+    let width = grid.ncols();
+    let seed = split_mix(seed);
+    grid.as_slice_mut()
+        .expect("grids are in standard layout")
+        .par_chunks_mut(CHUNK)
+        .enumerate()
+        .for_each(|(chunk, cells)| {
+            let mut rng = Xoshiro256PlusPlus::seed_from_u64(seed.wrapping_add(chunk as u64));
+            for (i, cell) in (chunk * CHUNK..).zip(cells) {
+                *cell = rng.gen_bool(probability(i % width, i / width).clamp(0., 1.));
+            }
+        });
+}
+
+/// Symmetry group of a symmetric soup.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Symmetry {
+    /// Half turn rotation
+    C2,
+    /// Quarter turn rotation
+    C4,
+    /// Left-right reflection
+    D2,
+    /// Left-right and top-bottom reflections
+    D4,
+    /// Rotations and reflections of the square
+    D8,
+}
+
+impl Symmetry {
+    /// Images of `(x, y)` in a `width` by `height` region, which must be a
+    /// square for the groups with quarter turns.
+    fn orbit(self, x: usize, y: usize, width: usize, height: usize) -> Vec<(usize, usize)> {
+        let (fx, fy) = (width - 1 - x, height - 1 - y);
+        match self {
+            Symmetry::C2 => vec![(x, y), (fx, fy)],
+            Symmetry::D2 => vec![(x, y), (fx, y)],
+            Symmetry::D4 => vec![(x, y), (fx, y), (x, fy), (fx, fy)],
+            Symmetry::C4 => vec![(x, y), (fy, x), (fx, fy), (y, fx)],
+            Symmetry::D8 => vec![
+                (x, y),
+                (fy, x),
+                (fx, fy),
+                (y, fx),
+                (y, x),
+                (fx, y),
+                (fy, fx),
+                (x, fy),
+            ],
+        }
+    }
+
+    fn is_square(self) -> bool {
+        matches!(self, Symmetry::C4 | Symmetry::D8)
+    }
+}
+
+/// Orientation of stripes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Direction {
+    /// Rows of cells
+    Horizontal,
+    /// Columns of cells
+    Vertical,
+    /// Lines going down from right to left
+    Diagonal,
+}
+
+/// Generator of the initial state of the boards, parsed from a name with
+/// optional comma separated parameters, as in `square:size=16,density=0.5`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Seeder {
+    /// Uniformly random cells.
+    Uniform {
+        /// Probability of a cell being alive, defaulting to `--fill`.
+        density: Option<f64>,
+    },
+    /// Random square in the middle of an empty board.
+    Square {
+        /// Side of the square in cells.
+        size: usize,
+        /// Probability of a cell of the square being alive.
+        density: f64,
+    },
+    /// Random cells repeated following a symmetry, in the middle of the
+    /// board.
+    Symmetric {
+        /// Symmetry group of the soup.
+        symmetry: Symmetry,
+        /// Side of the square holding the soup, defaulting to the largest
+        /// region of the board the symmetry allows.
+        size: Option<usize>,
+        /// Probability of a cell of the soup being alive.
+        density: f64,
+    },
+    /// Density varying smoothly following value noise.
+    Noise {
+        /// Approximate size of the features of the noise, in cells.
+        scale: f64,
+        /// Number of finer and finer layers of noise summed together.
+        octaves: u32,
+        /// Stretching of the noise around its mean.
+        contrast: f64,
+        /// Mean probability of a cell being alive, defaulting to `--fill`.
+        density: Option<f64>,
+    },
+    /// Random discs.
+    Blobs {
+        /// Number of discs.
+        count: usize,
+        /// Largest radius of the discs in cells, the smallest being half
+        /// of it.
+        radius: f64,
+        /// Probability of a cell of a disc being alive.
+        density: f64,
+    },
+    /// Parallel bands.
+    Stripes {
+        /// Width of the bands in cells.
+        width: usize,
+        /// Distance between the starts of two bands in cells.
+        period: usize,
+        /// Orientation of the bands.
+        direction: Direction,
+        /// Probability of a cell of a band being alive.
+        density: f64,
+    },
+}
+
+impl Default for Seeder {
+    fn default() -> Self {
+        Seeder::Uniform { density: None }
+    }
+}
+
+impl Seeder {
+    /// Fills `grid` from `seed`, `fill` being the density of the seeders
+    /// without one, failing if `fill` or the parameters are out of range.
+    pub fn seed(&self, grid: &mut Grid, seed: u64, fill: f64) -> anyhow::Result<()> {
+        self.check()?;
+        if !(0. ..=1.).contains(&fill) {
+            return Err(anyhow!("the fill density must be between 0 and 1"));
+        }
+        let (height, width) = grid.dim();
+        match *self {
+            Seeder::Uniform { density } => random_fill(grid, density.unwrap_or(fill), seed),
+            Seeder::Square { size, density } => {
+                let (x0, y0, w, h) = centered(width, height, size, size);
+                fill_with(grid, seed, |x, y| {
+                    let inside = (x0..x0 + w).contains(&x) && (y0..y0 + h).contains(&y);
+                    if inside {
+                        density
+                    } else {
+                        0.
+                    }
+                });
+            }
+            Seeder::Symmetric {
+                symmetry,
+                size,
+                density,
+            } => {
+                let (w, h) = match (size, symmetry.is_square()) {
+                    (Some(size), _) => (size, size),
+                    (None, true) => (width.min(height), width.min(height)),
+                    (None, false) => (width, height),
+                };
+                let (x0, y0, w, h) = centered(width, height, w, h);
+                // Clipping may have turned the square into a rectangle.
+                let (w, h) = match symmetry.is_square() {
+                    true => (w.min(h), w.min(h)),
+                    false => (w, h),
+                };
+                let mut cells = Grid::from_elem((h, w), false);
+                random_fill(&mut cells, density, seed);
+                grid.fill(false);
+                for y in 0..h {
+                    for x in 0..w {
+                        let (rx, ry) = symmetry
+                            .orbit(x, y, w, h)
+                            .into_iter()
+                            .min_by_key(|&(x, y)| (y, x))
+                            .unwrap();
+                        grid[(y0 + y, x0 + x)] = cells[(ry, rx)];
+                    }
+                }
+            }
+            Seeder::Noise {
+                scale,
+                octaves,
+                contrast,
+                density,
+            } => {
+                let density = density.unwrap_or(fill);
+                fill_with(grid, seed, |x, y| {
+                    let level = value_noise(seed, x as f64 / scale, y as f64 / scale, octaves);
+                    2. * density * ((level - 0.5) * contrast + 0.5)
+                });
+            }
+            Seeder::Blobs {
+                count,
+                radius,
+                density,
+            } => {
+                let mut rng = Xoshiro256PlusPlus::seed_from_u64(seed);
+                let blobs: Vec<_> = (0..count)
+                    .map(|_| {
+                        let center = (rng.gen_range(0..width), rng.gen_range(0..height));
+                        (center, rng.gen_range(radius / 2. ..=radius))
+                    })
+                    .collect();
+                fill_with(grid, seed, |x, y| {
+                    let inside = blobs.iter().any(|&((cx, cy), r)| {
+                        let (dx, dy) = (x as f64 - cx as f64, y as f64 - cy as f64);
+                        dx * dx + dy * dy <= r * r
+                    });
+                    if inside {
+                        density
+                    } else {
+                        0.
+                    }
+                });
+            }
+            Seeder::Stripes {
+                width,
+                period,
+                direction,
+                density,
+            } => fill_with(grid, seed, |x, y| {
+                let position = match direction {
+                    Direction::Horizontal => y,
+                    Direction::Vertical => x,
+                    Direction::Diagonal => x + y,
+                };
+                if position % period < width {
+                    density
+                } else {
+                    0.
+                }
+            }),
+        }
+        Ok(())
+    }
+
+    /// Fails if a parameter is out of range, which [`Seeder::seed`] could
+    /// not draw cells from.
+    pub fn check(&self) -> anyhow::Result<()> {
+        let density = |density: f64| match (0. ..=1.).contains(&density) {
+            true => Ok(()),
+            false => Err(anyhow!("the density must be between 0 and 1")),
+        };
+        let positive = |key: &str, value: f64| match value > 0. && value.is_finite() {
+            true => Ok(()),
+            false => Err(anyhow!("`{key}` must be positive")),
+        };
+        match *self {
+            Seeder::Uniform { density: None } => Ok(()),
+            Seeder::Uniform {
+                density: Some(value),
+            }
+            | Seeder::Square { density: value, .. }
+            | Seeder::Symmetric { density: value, .. } => density(value),
+            Seeder::Noise {
+                scale,
+                octaves,
+                contrast,
+                density: value,
+            } => {
+                positive("scale", scale)?;
+                positive("contrast", contrast)?;
+                if octaves == 0 {
+                    return Err(anyhow!("`octaves` must be positive"));
+                }
+                value.map_or(Ok(()), density)
+            }
+            Seeder::Blobs {
+                radius,
+                density: value,
+                ..
+            } => {
+                positive("radius", radius)?;
+                density(value)
+            }
+            Seeder::Stripes {
+                width,
+                period,
+                density: value,
+                ..
+            } => {
+                if width == 0 || period == 0 {
+                    return Err(anyhow!("`width` and `period` must be positive"));
+                }
+                if width > period {
+                    return Err(anyhow!("the width must not exceed the period"));
+                }
+                density(value)
+            }
+        }
+    }
+}
+
+/// Origin and dimensions of a `w` by `h` region centred on a `width` by
+/// `height` board, clipped to it.
+fn centered(width: usize, height: usize, w: usize, h: usize) -> (usize, usize, usize, usize) {
+    let (w, h) = (w.min(width), h.min(height));
+    ((width - w) / 2, (height - h) / 2, w, h)
+}
+
+/// Value noise between 0 and 1 at `(x, y)`, with features about 1 unit
+/// large, summed over `octaves` halving in size and amplitude.
+fn value_noise(seed: u64, x: f64, y: f64, octaves: u32) -> f64 {
+    let (mut total, mut amplitudes) = (0., 0.);
+    for octave in 0..octaves {
+        let (frequency, amplitude) = (2f64.powi(octave as i32), 0.5f64.powi(octave as i32));
+        let (x, y) = (x * frequency, y * frequency);
+        let (ix, iy) = (x.floor(), y.floor());
+        let lattice = |dx: f64, dy: f64| {
+            let hash = split_mix(
+                seed ^ split_mix(octave as u64)
+                    ^ split_mix((ix + dx) as i64 as u64)
+                    ^ split_mix((iy + dy) as i64 as u64).rotate_left(32),
+            );
+            (hash >> 11) as f64 / (1u64 << 53) as f64
+        };
+        let smooth = |t: f64| t * t * (3. - 2. * t);
+        let (tx, ty) = (smooth(x - ix), smooth(y - iy));
+        let top = lattice(0., 0.) + (lattice(1., 0.) - lattice(0., 0.)) * tx;
+        let bottom = lattice(0., 1.) + (lattice(1., 1.) - lattice(0., 1.)) * tx;
+        total += amplitude * (top + (bottom - top) * ty);
+        amplitudes += amplitude;
+    }
+    total / amplitudes
+}
+
+/// Comma separated `key=value` parameters of a seeder.
+struct Parameters<'a>(Vec<(&'a str, &'a str)>);
+
+impl<'a> Parameters<'a> {
+    fn parse(parameters: &'a str) -> anyhow::Result<Self> {
+        parameters
+            .split(',')
+            .filter(|parameter| !parameter.is_empty())
+            .map(|parameter| {
+                parameter
+                    .split_once('=')
+                    .ok_or_else(|| anyhow!("expected key=value, got `{parameter}`"))
+            })
+            .collect::<anyhow::Result<_>>()
+            .map(Self)
+    }
+
+    fn take<T: FromStr>(&mut self, key: &str) -> anyhow::Result<Option<T>> {
+        let Some(i) = self.0.iter().position(|&(k, _)| k == key) else {
+            return Ok(None);
+        };
+        let (_, value) = self.0.remove(i);
+        let value = value
+            .parse()
+            .map_err(|_| anyhow!("invalid value `{value}` for `{key}`"))?;
+        Ok(Some(value))
+    }
+
+    fn density(&mut self) -> anyhow::Result<Option<f64>> {
+        match self.take("density")? {
+            Some(density) if !(0. ..=1.).contains(&density) => {
+                Err(anyhow!("the density must be between 0 and 1"))
+            }
+            density => Ok(density),
+        }
+    }
+
+    fn positive<T: FromStr + Default + PartialOrd>(
+        &mut self,
+        key: &str,
+        default: T,
+    ) -> anyhow::Result<T> {
+        match self.take(key)? {
+            Some(value) if value <= T::default() => Err(anyhow!("`{key}` must be positive")),
+            value => Ok(value.unwrap_or(default)),
+        }
+    }
+
+    fn finish(self) -> anyhow::Result<()> {
+        match self.0.first() {
+            Some((key, _)) => Err(anyhow!("unknown parameter `{key}`")),
+            None => Ok(()),
+        }
+    }
+}
+
+fn parse_enum<T: ValueEnum>(value: &str) -> anyhow::Result<T> {
+    T::from_str(value, true).map_err(|_| anyhow!("invalid value `{value}`"))
+}
+
+impl FromStr for Seeder {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, parameters) = s.split_once(':').unwrap_or((s, ""));
+        let parse = || -> anyhow::Result<Self> {
+            let mut parameters = Parameters::parse(parameters)?;
+            let seeder = match name {
+                "uniform" => Seeder::Uniform {
+                    density: parameters.density()?,
+                },
+                "square" => Seeder::Square {
+                    size: parameters.positive("size", 16)?,
+                    density: parameters.density()?.unwrap_or(0.5),
+                },
+                "symmetric" => Seeder::Symmetric {
+                    symmetry: match parameters.take::<String>("symmetry")? {
+                        Some(symmetry) => parse_enum(&symmetry)?,
+                        None => Symmetry::C2,
+                    },
+                    size: match parameters.positive("size", 0)? {
+                        0 => None,
+                        size => Some(size),
+                    },
+                    density: parameters.density()?.unwrap_or(0.5),
+                },
+                "noise" => Seeder::Noise {
+                    scale: parameters.positive("scale", 16.)?,
+                    octaves: parameters.positive("octaves", 3)?,
+                    contrast: parameters.positive("contrast", 2.)?,
+                    density: parameters.density()?,
+                },
+                "blobs" => Seeder::Blobs {
+                    count: parameters.positive("count", 8)?,
+                    radius: parameters.positive("radius", 8.)?,
+                    density: parameters.density()?.unwrap_or(0.5),
+                },
+                "stripes" => Seeder::Stripes {
+                    width: parameters.positive("width", 2)?,
+                    period: parameters.positive("period", 8)?,
+                    direction: match parameters.take::<String>("direction")? {
+                        Some(direction) => parse_enum(&direction)?,
+                        None => Direction::Horizontal,
+                    },
+                    density: parameters.density()?.unwrap_or(1.),
+                },
+                _ => {
+                    return Err(anyhow!(
+                        "expected uniform, square, symmetric, noise, blobs or stripes"
+                    ))
+                }
+            };
+            parameters.finish()?;
+            seeder.check()?;
+            Ok(seeder)
+        };
+        parse().map_err(|err| anyhow!("invalid seeder `{s}`: {err}"))
+    }
+}
+
+impl fmt::Display for Seeder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Seeder::Uniform { density: None } => write!(f, "uniform"),
+            Seeder::Uniform {
+                density: Some(density),
+            } => write!(f, "uniform:density={density}"),
+            Seeder::Square { size, density } => write!(f, "square:size={size},density={density}"),
+            Seeder::Symmetric {
+                symmetry,
+                size,
+                density,
+            } => {
+                write!(f, "symmetric:symmetry={}", name(symmetry))?;
+                if let Some(size) = size {
+                    write!(f, ",size={size}")?;
+                }
+                write!(f, ",density={density}")
+            }
+            Seeder::Noise {
+                scale,
+                octaves,
+                contrast,
+                density,
+            } => {
+                write!(
+                    f,
+                    "noise:scale={scale},octaves={octaves},contrast={contrast}"
+                )?;
+                if let Some(density) = density {
+                    write!(f, ",density={density}")?;
+                }
+                Ok(())
+            }
+            Seeder::Blobs {
+                count,
+                radius,
+                density,
+            } => write!(f, "blobs:count={count},radius={radius},density={density}"),
+            Seeder::Stripes {
+                width,
+                period,
+                direction,
+                density,
+            } => write!(
+                f,
+                "stripes:width={width},period={period},direction={},density={density}",
+                name(direction)
+            ),
+        }
+    }
+}
+
+/// Name of `value` on the command line.
+fn name(value: impl ValueEnum) -> String {
+    value.to_possible_value().unwrap().get_name().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::life::new_grid;
+
+    fn fill_with_threads(threads: usize, seed: u64) -> Grid {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap();
+        let mut grid = new_grid(300, 100);
+        pool.install(|| random_fill(&mut grid, 0.3, seed));
+        grid
+    }
+
+    fn population(grid: &Grid) -> usize {
+        grid.iter().filter(|&&alive| alive).count()
+    }
+
+    #[test]
+    fn fill_depends_on_seed_only() {
+        let grid = fill_with_threads(1, 42);
+        assert_eq!(fill_with_threads(4, 42), grid);
+        assert_ne!(fill_with_threads(4, 43), grid);
+        let density = population(&grid) as f64 / grid.len() as f64;
+        assert!((density - 0.3).abs() < 0.02, "{density}");
+    }
+
+    #[test]
+    fn sequence_starts_with_run_seed() {
+        let mut sequence = SeedSequence::new(7);
+        let seeds: Vec<_> = (0..3).map(|_| sequence.next_seed()).collect();
+        assert_eq!(seeds[0], 7);
+        assert_ne!(seeds[1], seeds[2]);
+        let mut replay = SeedSequence::new(seeds[1]);
+        assert_eq!(replay.next_seed(), seeds[1]);
+        assert_eq!(replay.next_seed(), seeds[2]);
+    }
+
+    #[test]
+    fn parses_and_displays_seeders() {
+        for spec in [
+            "uniform",
+            "square:size=16,density=0.5",
+            "symmetric:symmetry=d8,size=20,density=0.4",
+            "noise:scale=10,octaves=2,contrast=3",
+            "blobs:count=3,radius=5,density=0.5",
+            "stripes:width=1,period=4,direction=diagonal,density=1",
+        ] {
+            assert_eq!(spec.parse::<Seeder>().unwrap().to_string(), spec);
+        }
+        assert_eq!(
+            "symmetric:symmetry=C4".parse::<Seeder>().unwrap(),
+            Seeder::Symmetric {
+                symmetry: Symmetry::C4,
+                size: None,
+                density: 0.5
+            }
+        );
+        for invalid in [
+            "fractal",
+            "square:size=0",
+            "square:density=2",
+            "uniform:size=3",
+            "symmetric:symmetry=c3",
+            "stripes:width=9",
+            "blobs:count",
+            "noise:scale=NaN",
+            "blobs:radius=inf",
+        ] {
+            assert!(invalid.parse::<Seeder>().is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn rejects_out_of_range_fields() {
+        let mut grid = new_grid(8, 8);
+        for seeder in [
+            Seeder::Stripes {
+                width: 0,
+                period: 0,
+                direction: Direction::Vertical,
+                density: 1.,
+            },
+            Seeder::Blobs {
+                count: 1,
+                radius: -1.,
+                density: 0.5,
+            },
+            Seeder::Noise {
+                scale: 0.,
+                octaves: 3,
+                contrast: 2.,
+                density: None,
+            },
+            Seeder::Square {
+                size: 4,
+                density: f64::NAN,
+            },
+        ] {
+            assert!(seeder.seed(&mut grid, 0, 0.15).is_err(), "{seeder:?}");
+        }
+        assert!(Seeder::default().seed(&mut grid, 0, f64::NAN).is_err());
+    }
+
+    #[test]
+    fn square_soup_is_centered() {
+        let mut grid = new_grid(40, 30);
+        let seeder: Seeder = "square:size=10,density=1".parse().unwrap();
+        seeder.seed(&mut grid, 1, 0.15).unwrap();
+        assert_eq!(population(&grid), 100);
+        assert!(grid[(10, 15)] && grid[(19, 24)]);
+        assert!(!grid[(9, 15)] && !grid[(19, 25)]);
+    }
+
+    #[test]
+    fn symmetric_soups_are_symmetric() {
+        for symmetry in Symmetry::value_variants() {
+            let seeder = Seeder::Symmetric {
+                symmetry: *symmetry,
+                size: None,
+                density: 0.5,
+            };
+            let mut grid = new_grid(24, 20);
+            seeder.seed(&mut grid, 3, 0.15).unwrap();
+            let (w, h, x0) = match symmetry.is_square() {
+                true => (20, 20, 2),
+                false => (24, 20, 0),
+            };
+            assert!(population(&grid) > 0);
+            for y in 0..h {
+                for x in 0..w {
+                    for (ox, oy) in symmetry.orbit(x, y, w, h) {
+                        assert_eq!(grid[(y, x0 + x)], grid[(oy, x0 + ox)], "{symmetry:?}");
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn density_fields_are_reproducible() {
+        for spec in ["noise", "blobs", "stripes:direction=vertical,density=1"] {
+            let seeder: Seeder = spec.parse().unwrap();
+            let (mut a, mut b) = (new_grid(64, 48), new_grid(64, 48));
+            seeder.seed(&mut a, 9, 0.3).unwrap();
+            seeder.seed(&mut b, 9, 0.3).unwrap();
+            assert_eq!(a, b, "{spec}");
+            assert!(population(&a) > 0, "{spec}");
+        }
+        let mut stripes = new_grid(8, 2);
+        "stripes:width=2,period=4,direction=vertical"
+            .parse::<Seeder>()
+            .unwrap()
+            .seed(&mut stripes, 0, 0.)
+            .unwrap();
+        assert_eq!(
+            stripes.row(1).to_vec(),
+            [true, true, false, false, true, true, false, false]
+        );
+    }
+}